@@ -0,0 +1,6 @@
+fn main() {
+    // `--cfg tracing_glog_unstable` enables error backtraces on nightly Rust. Declaring
+    // it here rather than in a `[lints]` table keeps the crate building with Cargo
+    // versions older than the one that added `check-cfg` (1.80), which ignore this.
+    println!("cargo:rustc-check-cfg=cfg(tracing_glog_unstable)");
+}
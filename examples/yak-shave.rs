@@ -1,23 +1,23 @@
 use structopt::StructOpt;
 use thiserror::Error;
 use tracing::{debug, error, info, span, trace, warn, Level};
-use tracing_glog::{Glog, GlogFields};
+use tracing_glog::{ColorChoice, Glog, GlogFields};
 
-/// To run with ANSI colors, run:
+/// To always run with ANSI colors, run:
 /// ```bash
-/// cargo run --example yak-shave -- --with-ansi
+/// cargo run --example yak-shave -- --color always
 /// ```
 ///
 /// To run without ANSI colors, run:
 /// ```bash
-/// cargo run --example yak-shave
+/// cargo run --example yak-shave -- --color never
 /// ```
 
 #[derive(Debug, structopt::StructOpt)]
 struct Args {
-    /// Whether to run this example with or without ANSI colors.
-    #[structopt(long)]
-    with_ansi: bool,
+    /// When to use ANSI colors: `auto`, `always` or `never`.
+    #[structopt(long, default_value = "auto")]
+    color: ColorChoice,
 
     /// Whether tracing-glog should include the span context.
     #[structopt(long)]
@@ -28,7 +28,7 @@ fn main() {
     let args = Args::from_args();
 
     tracing_subscriber::fmt()
-        .with_ansi(args.with_ansi)
+        .with_ansi(args.color.use_ansi(&std::io::stdout()))
         .event_format(Glog::default().with_span_context(args.with_span_context))
         .fmt_fields(GlogFields::default())
         .init();
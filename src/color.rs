@@ -0,0 +1,138 @@
+use std::{env, error::Error, ffi::OsStr, fmt, io::IsTerminal, str::FromStr};
+
+/// When to use ANSI colors.
+///
+/// Whether [`Glog`] and [`GlogFields`] print colors is decided by the [`fmt::Layer`] or
+/// [`fmt::Subscriber`] they are used with, through its `with_ansi` method. A
+/// `ColorChoice` resolves to the value to pass to `with_ansi` for a given destination:
+///
+/// ```
+/// use tracing_glog::{ColorChoice, Glog, GlogFields};
+///
+/// tracing_subscriber::fmt()
+///     .with_writer(std::io::stderr)
+///     .with_ansi(ColorChoice::Auto.use_ansi(&std::io::stderr()))
+///     .event_format(Glog::default())
+///     .fmt_fields(GlogFields::default())
+///     .init();
+/// ```
+///
+/// When logging to several destinations, each layer's choice should be resolved
+/// against its own destination, so that, for example, a terminal gets colors while a
+/// log file does not:
+///
+/// ```no_run
+/// use tracing_glog::{ColorChoice, Glog, GlogFields};
+/// use tracing_subscriber::{fmt, prelude::*, Registry};
+///
+/// let file = std::fs::File::create("app.log").expect("Unable to create log file");
+/// let stderr = fmt::Layer::default()
+///     .with_writer(std::io::stderr)
+///     .with_ansi(ColorChoice::Auto.use_ansi(&std::io::stderr()))
+///     .event_format(Glog::default())
+///     .fmt_fields(GlogFields::default());
+/// let file_ansi = ColorChoice::Auto.use_ansi(&file);
+/// let file = fmt::Layer::default()
+///     .with_writer(file)
+///     .with_ansi(file_ansi)
+///     .event_format(Glog::default())
+///     .fmt_fields(GlogFields::default());
+///
+/// let subscriber = Registry::default().with(stderr).with(file);
+/// tracing::subscriber::set_global_default(subscriber).expect("Unable to set global subscriber");
+/// ```
+///
+/// Choices can also be parsed from `auto`, `always` and `never`, as commonly accepted
+/// by a `--color` command line flag.
+///
+/// [`Glog`]: crate::Glog
+/// [`GlogFields`]: crate::GlogFields
+/// [`fmt::Layer`]: tracing_subscriber::fmt::Layer
+/// [`fmt::Subscriber`]: tracing_subscriber::fmt::Subscriber
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Use colors if the environment allows them and the destination is a terminal.
+    ///
+    /// Colors are used if `CLICOLOR_FORCE` is set to anything other than `0`. Otherwise,
+    /// they are not used if [`NO_COLOR`] is set, if `CLICOLOR` is set to `0` or if
+    /// `TERM` is set to `dumb`. If none of those are set, colors are used only if the
+    /// destination is a terminal.
+    ///
+    /// [`NO_COLOR`]: https://no-color.org
+    #[default]
+    Auto,
+    /// Always use colors, regardless of the environment.
+    Always,
+    /// Never use colors.
+    Never,
+}
+
+impl ColorChoice {
+    /// Returns whether or not colors should be used when writing to `destination`.
+    ///
+    /// This is always false when the `ansi` feature is disabled, since
+    /// `tracing-subscriber` can't print colors without it.
+    pub fn use_ansi<W: IsTerminal>(self, destination: &W) -> bool {
+        if !cfg!(feature = "ansi") {
+            return false;
+        }
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                if is_set(env::var_os("CLICOLOR_FORCE"), |value| value != "0") {
+                    true
+                } else if is_set(env::var_os("NO_COLOR"), |_| true)
+                    || is_set(env::var_os("CLICOLOR"), |value| value == "0")
+                    || is_set(env::var_os("TERM"), |value| value == "dumb")
+                {
+                    false
+                } else {
+                    destination.is_terminal()
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether an environment variable is set to a non-empty value that matches
+/// `predicate`.
+fn is_set(value: Option<impl AsRef<OsStr>>, predicate: impl Fn(&OsStr) -> bool) -> bool {
+    value.is_some_and(|value| {
+        let value = value.as_ref();
+        !value.is_empty() && predicate(value)
+    })
+}
+
+impl FromStr for ColorChoice {
+    type Err = ParseColorChoiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(ParseColorChoiceError {
+                value: other.to_string(),
+            }),
+        }
+    }
+}
+
+/// An error returned when parsing a [`ColorChoice`] fails.
+#[derive(Debug)]
+pub struct ParseColorChoiceError {
+    value: String,
+}
+
+impl fmt::Display for ParseColorChoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid color choice `{}`, expected `auto`, `always` or `never`",
+            self.value
+        )
+    }
+}
+
+impl Error for ParseColorChoiceError {}
@@ -0,0 +1,221 @@
+use crate::{
+    escape::{Escape, Escaped},
+    Redaction,
+};
+use std::{error::Error, fmt};
+use tracing::field::Field;
+
+/// How [`GlogFields`] prints errors recorded as fields, such as with
+/// `tracing::error!(error = &err as &dyn Error)`.
+///
+/// Defaults to [`ErrorFormat::Sources`].
+///
+/// ```
+/// use tracing_glog::{ErrorFormat, GlogFields};
+///
+/// let fields = GlogFields::default().with_error_format(ErrorFormat::Chain(" <- ".into()));
+/// ```
+///
+/// [`GlogFields`]: crate::GlogFields
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Only the error itself, without its sources: `error: failed to shave yak`.
+    TopLevel,
+    /// The error, followed by a list of its sources if it has any:
+    /// `error: failed to shave yak, error.sources: [out of cash, bank closed]`.
+    #[default]
+    Sources,
+    /// The error and its sources, separated by the given separator:
+    /// `error: failed to shave yak: out of cash: bank closed` with `": "`.
+    Chain(String),
+    /// The error and its sources in their alternate form, `{:#}`, separated by `: `, as
+    /// `anyhow` prints errors with `{:#}`.
+    Alternate,
+    /// The error's `Debug` output.
+    Debug,
+    /// The error, followed by its numbered sources on the following lines, as `anyhow`
+    /// prints errors with `{:?}`:
+    ///
+    /// <pre>
+    /// E0731 16:23:45.674465 990039 examples/yak-shave.rs:85] failed to shave yak, error: failed to shave yak
+    /// Caused by:
+    ///     0: out of cash
+    ///     1: bank closed
+    /// </pre>
+    ///
+    /// The lines are printed according to [`Glog::with_multiline`], and an error
+    /// without sources is printed on a single line.
+    ///
+    /// [`Glog::with_multiline`]: crate::Glog::with_multiline
+    CausedBy,
+}
+
+/// Formats an error recorded as `field` according to `format`.
+pub(crate) struct FormatError<'a> {
+    pub(crate) error: &'a (dyn Error + 'static),
+    pub(crate) field: &'a Field,
+    pub(crate) format: &'a ErrorFormat,
+    pub(crate) redaction: Option<&'a Redaction>,
+    #[cfg_attr(not(tracing_glog_unstable), allow(dead_code))]
+    pub(crate) backtraces: bool,
+    /// Which characters to escape in the messages of the errors. Only used for
+    /// multi-line errors, since otherwise the whole value is escaped.
+    pub(crate) escape: Escape,
+}
+
+impl<'a> FormatError<'a> {
+    /// Returns whether or not the error is printed on several lines.
+    pub(crate) fn is_multiline(&self) -> bool {
+        (*self.format == ErrorFormat::CausedBy && self.error.source().is_some())
+            || self.backtrace().is_some()
+    }
+
+    /// Returns the backtrace of the first error in the chain that provides a captured
+    /// one, if backtraces are enabled.
+    #[cfg(tracing_glog_unstable)]
+    fn backtrace(&self) -> Option<&'a std::backtrace::Backtrace> {
+        use std::backtrace::{Backtrace, BacktraceStatus};
+
+        if !self.backtraces {
+            return None;
+        }
+        self.chain()
+            .filter_map(std::error::request_ref::<Backtrace>)
+            .find(|backtrace| backtrace.status() == BacktraceStatus::Captured)
+    }
+
+    #[cfg(not(tracing_glog_unstable))]
+    fn backtrace(&self) -> Option<&'a std::backtrace::Backtrace> {
+        None
+    }
+
+    /// Returns the list of the sources of the error, such as `[out of cash, bank
+    /// closed]`, if it has any.
+    pub(crate) fn sources(&self) -> Option<Sources<'_, 'a>> {
+        self.error.source().map(|_| Sources(self))
+    }
+
+    fn chain(&self) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+        std::iter::successors(Some(self.error), |&error| error.source())
+    }
+
+    /// Writes an error of the chain in its `Display` form, or its alternate form if
+    /// `alternate` is set.
+    fn write_error(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        error: &(dyn Error + 'static),
+        alternate: bool,
+    ) -> fmt::Result {
+        let error = RedactedError(error, self.redaction);
+        let escape = self.escape;
+        match alternate {
+            true => write!(
+                f,
+                "{:?}",
+                Escaped {
+                    value: &format_args!("{error:#}"),
+                    escape
+                }
+            ),
+            false => write!(
+                f,
+                "{:?}",
+                Escaped {
+                    value: &format_args!("{error}"),
+                    escape
+                }
+            ),
+        }
+    }
+}
+
+impl<'a> fmt::Display for FormatError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            ErrorFormat::TopLevel => self.write_error(f, self.error, false)?,
+            ErrorFormat::Sources => {
+                self.write_error(f, self.error, false)?;
+                if let Some(sources) = self.sources() {
+                    write!(f, ", {}.sources: {sources}", self.field)?;
+                }
+            }
+            ErrorFormat::Chain(separator) => {
+                for (idx, error) in self.chain().enumerate() {
+                    if idx > 0 {
+                        f.write_str(separator)?;
+                    }
+                    self.write_error(f, error, false)?;
+                }
+            }
+            ErrorFormat::Alternate => {
+                for (idx, error) in self.chain().enumerate() {
+                    if idx > 0 {
+                        f.write_str(": ")?;
+                    }
+                    self.write_error(f, error, true)?;
+                }
+            }
+            ErrorFormat::Debug => match self.redaction.and_then(|r| r.redact_value(self.error)) {
+                Some(redacted) => write!(f, "{redacted}")?,
+                None => write!(
+                    f,
+                    "{:?}",
+                    Escaped {
+                        value: self.error,
+                        escape: self.escape
+                    }
+                )?,
+            },
+            ErrorFormat::CausedBy => {
+                self.write_error(f, self.error, false)?;
+                if self.error.source().is_some() {
+                    f.write_str("\nCaused by:")?;
+                    for (idx, error) in self.chain().skip(1).enumerate() {
+                        write!(f, "\n    {idx}: ")?;
+                        self.write_error(f, error, false)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(backtrace) = self.backtrace() {
+            write!(
+                f,
+                "\nStack backtrace:\n{}",
+                backtrace.to_string().trim_end()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The sources of a [`FormatError`], as a list.
+pub(crate) struct Sources<'f, 'a>(&'f FormatError<'a>);
+
+impl<'f, 'a> fmt::Display for Sources<'f, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for error in self.0.chain().skip(1) {
+            let error = RedactedError(error, self.0.redaction);
+            list.entry(&Escaped {
+                value: &format_args!("{error}"),
+                escape: self.0.escape,
+            });
+        }
+        list.finish()
+    }
+}
+
+/// Renders an error, or its replacement if its message matches a value pattern of
+/// the [`Redaction`].
+struct RedactedError<'a>(&'a (dyn Error + 'static), Option<&'a Redaction>);
+
+impl<'a> fmt::Display for RedactedError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1.and_then(|redaction| redaction.redact_value(self.0)) {
+            Some(redacted) => write!(f, "{redacted}"),
+            None => fmt::Display::fmt(self.0, f),
+        }
+    }
+}
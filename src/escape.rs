@@ -0,0 +1,159 @@
+use std::fmt::{self, Write};
+
+/// When to escape control characters in messages and field values.
+///
+/// Messages and unquoted strings are otherwise written as they are, so a value
+/// containing a newline can forge a new line in the log, and one containing escape
+/// codes can spoof colors. When escaping, `\r`, `\n`, `\t`, `ESC`, other control
+/// characters and the Unicode bidirectional overrides are written as escape sequences,
+/// such as `\n` and `\u{1b}`, in messages, field values and span fields.
+///
+/// Newlines in messages and field values are left for [`Glog::with_multiline`] to
+/// handle, unless it is set to [`Multiline::Keep`].
+///
+/// Set with [`GlogFields::with_escaping`].
+///
+/// [`GlogFields::with_escaping`]: crate::GlogFields::with_escaping
+/// [`Glog::with_multiline`]: crate::Glog::with_multiline
+/// [`Multiline::Keep`]: crate::Multiline::Keep
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Escaping {
+    /// Escape control characters when ANSI escape codes are disabled.
+    ///
+    /// [`GlogFields`] can't tell where its output goes, so this relies on the
+    /// `with_ansi` setting of the layer, which [`ColorChoice::Auto`] disables when the
+    /// destination isn't a terminal.
+    ///
+    /// [`GlogFields`]: crate::GlogFields
+    /// [`ColorChoice::Auto`]: crate::ColorChoice::Auto
+    #[default]
+    Auto,
+    /// Always escape control characters.
+    Always,
+    /// Never escape control characters.
+    Never,
+}
+
+impl Escaping {
+    /// Returns whether or not to escape control characters in output that has ANSI
+    /// escape codes enabled or not.
+    pub(crate) fn escapes(self, ansi: bool) -> bool {
+        match self {
+            Escaping::Auto => !ansi,
+            Escaping::Always => true,
+            Escaping::Never => false,
+        }
+    }
+}
+
+/// Which characters of a value are escaped.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escape {
+    /// Nothing is escaped.
+    Nothing,
+    /// Control characters are escaped, except for newlines (`\n` and `\r\n`), which
+    /// are left for [`Multiline`] to handle.
+    ///
+    /// [`Multiline`]: crate::Multiline
+    AllButNewlines,
+    /// Only newlines and carriage returns are escaped, so that a value stays on one
+    /// line without escaping anything else.
+    Newlines,
+    /// Control characters are escaped.
+    All,
+}
+
+impl Escape {
+    /// Returns whether or not `c` is escaped.
+    pub(crate) fn escapes(self, c: char) -> bool {
+        match self {
+            Escape::Nothing => false,
+            Escape::AllButNewlines => c != '\n' && needs_escape(c),
+            Escape::Newlines => matches!(c, '\n' | '\r'),
+            Escape::All => needs_escape(c),
+        }
+    }
+
+    /// Returns the escaping that also escapes newlines, for values that must stay on
+    /// one line.
+    pub(crate) fn with_newlines(self) -> Escape {
+        match self {
+            Escape::Nothing | Escape::Newlines => Escape::Newlines,
+            Escape::AllButNewlines | Escape::All => Escape::All,
+        }
+    }
+}
+
+/// Returns whether or not `c` is escaped.
+pub(crate) fn needs_escape(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Writes `c`, which needs to be escaped, as an escape sequence.
+pub(crate) fn write_escaped(f: &mut dyn Write, c: char) -> fmt::Result {
+    match c {
+        '\n' => f.write_str("\\n"),
+        '\r' => f.write_str("\\r"),
+        '\t' => f.write_str("\\t"),
+        c => write!(f, "\\u{{{:x}}}", c as u32),
+    }
+}
+
+/// Formats a value, escaping the characters selected by `escape`.
+pub(crate) struct Escaped<'a> {
+    pub(crate) value: &'a dyn fmt::Debug,
+    pub(crate) escape: Escape,
+}
+
+impl<'a> fmt::Debug for Escaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.escape {
+            Escape::Nothing => self.value.fmt(f),
+            escape => write!(EscapeWriter(f, escape), "{:?}", self.value),
+        }
+    }
+}
+
+/// Escapes the characters selected by the [`Escape`] written to the inner writer.
+struct EscapeWriter<'a>(&'a mut dyn Write, Escape);
+
+impl<'a> Write for EscapeWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        let mut chars = s.char_indices().peekable();
+        while let Some((idx, c)) = chars.next() {
+            // the carriage return of a `\r\n` is part of the newline.
+            let crlf = c == '\r' && matches!(chars.peek(), Some((_, '\n')));
+            if self.1.escapes(c) && !(crlf && self.1 == Escape::AllButNewlines) {
+                self.0.write_str(&s[start..idx])?;
+                write_escaped(self.0, c)?;
+                start = idx + c.len_utf8();
+            }
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+/// Formats a string in quotes, escaping quotes and backslashes as well as the
+/// characters selected by `escape`, so that escaping is applied only once.
+pub(crate) struct Quoted<'a> {
+    pub(crate) value: &'a str,
+    pub(crate) escape: Escape,
+}
+
+impl<'a> fmt::Display for Quoted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.value.chars() {
+            match c {
+                '"' | '\\' => {
+                    f.write_char('\\')?;
+                    f.write_char(c)?;
+                }
+                c if self.escape.escapes(c) => write_escaped(f, c)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
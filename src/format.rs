@@ -1,83 +1,452 @@
-#[cfg(feature = "ansi")]
-use nu_ansi_term::{Color, Style};
-use std::fmt;
-use tracing::{Level, Metadata};
-use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
+use crate::{
+    hyperlink::{Hyperlink, Hyperlinks},
+    nu_ansi_term::Style,
+    theme::Theme,
+};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tracing::{
+    field::{display, Field, FieldSet, Value, Visit},
+    Event, Level, Metadata,
+};
+use tracing_subscriber::fmt::{
+    format::{FmtSpan, Writer},
+    time::FormatTime,
+};
 
 use tracing_subscriber::fmt::time::{ChronoLocal, ChronoUtc};
 
+/// The labels used to indicate the level of each event.
+///
+/// Labels are printed immediately before the timestamp, as glog does. Any trailing
+/// whitespace in a label is printed without the label's style, so labels longer than a
+/// single character can end in a space to separate them from the timestamp.
+///
+/// Labels can be borrowed or owned, so they can be loaded from configuration at
+/// runtime:
+///
+/// ```
+/// use tracing::Level;
+/// use tracing_glog::{FormatLevelChars, Glog};
+///
+/// let warn = String::from("WARN ");
+/// let glog = Glog::default().with_format_level_chars(
+///     FormatLevelChars::names().with_label(Level::WARN, warn),
+/// );
+/// ```
+///
+/// Since 0.5, the labels are private, so `FormatLevelChars` can no longer be created
+/// with a struct literal. Use [`FormatLevelChars::new`] or one of the presets instead.
+#[derive(Clone, Debug)]
 pub struct FormatLevelChars {
-    pub trace: &'static str,
-    pub debug: &'static str,
-    pub info: &'static str,
-    pub warn: &'static str,
-    pub error: &'static str,
+    labels: [Cow<'static, str>; 5],
+    styles: [Option<Style>; 5],
 }
 
 impl FormatLevelChars {
+    /// Creates labels from the label of each level, from the least to the most severe:
+    ///
+    /// ```
+    /// use tracing_glog::FormatLevelChars;
+    ///
+    /// let level_chars = FormatLevelChars::new("T", "D", "I", "W", "E");
+    /// ```
+    pub fn new(
+        trace: impl Into<Cow<'static, str>>,
+        debug: impl Into<Cow<'static, str>>,
+        info: impl Into<Cow<'static, str>>,
+        warn: impl Into<Cow<'static, str>>,
+        error: impl Into<Cow<'static, str>>,
+    ) -> FormatLevelChars {
+        FormatLevelChars {
+            labels: [
+                trace.into(),
+                debug.into(),
+                info.into(),
+                warn.into(),
+                error.into(),
+            ],
+            styles: [None; 5],
+        }
+    }
+
     pub const fn const_default() -> FormatLevelChars {
+        FormatLevelChars::from_static(["T", "D", "I", "W", "E"])
+    }
+
+    /// The initial character of each level, such as `I` and `W`. This is the default.
+    pub const fn letters() -> FormatLevelChars {
+        FormatLevelChars::const_default()
+    }
+
+    /// glog's full names for each level, such as `INFO` and `WARNING`, followed by a
+    /// space.
+    pub const fn names() -> FormatLevelChars {
+        FormatLevelChars::from_static(["TRACE ", "DEBUG ", "INFO ", "WARNING ", "ERROR "])
+    }
+
+    /// glog's full names for each level padded to the same width, followed by a space,
+    /// so that the timestamps of all levels line up.
+    pub const fn padded_names() -> FormatLevelChars {
+        FormatLevelChars::from_static(["TRACE   ", "DEBUG   ", "INFO    ", "WARNING ", "ERROR   "])
+    }
+
+    /// Sets the label of a single level.
+    pub fn with_label(mut self, level: Level, label: impl Into<Cow<'static, str>>) -> Self {
+        self.labels[index(level)] = label.into();
+        self
+    }
+
+    /// Sets the style of a single level's label, overriding the [`Theme`]'s style for
+    /// the level.
+    #[cfg(feature = "ansi")]
+    pub fn with_style(mut self, level: Level, style: Style) -> Self {
+        self.styles[index(level)] = Some(style);
+        self
+    }
+
+    /// Returns the label of `level`.
+    pub fn label(&self, level: Level) -> &str {
+        &self.labels[index(level)]
+    }
+
+    const fn from_static(labels: [&'static str; 5]) -> FormatLevelChars {
         FormatLevelChars {
-            trace: "T",
-            debug: "D",
-            info: "I",
-            warn: "W",
-            error: "E",
+            labels: [
+                Cow::Borrowed(labels[0]),
+                Cow::Borrowed(labels[1]),
+                Cow::Borrowed(labels[2]),
+                Cow::Borrowed(labels[3]),
+                Cow::Borrowed(labels[4]),
+            ],
+            styles: [None; 5],
         }
     }
 }
 
+fn index(level: Level) -> usize {
+    match level {
+        Level::TRACE => 0,
+        Level::DEBUG => 1,
+        Level::INFO => 2,
+        Level::WARN => 3,
+        Level::ERROR => 4,
+    }
+}
+
 impl Default for FormatLevelChars {
     fn default() -> FormatLevelChars {
         FormatLevelChars::const_default()
     }
 }
 
-pub(crate) const DEFAULT_FORMAT_LEVEL_CHARS: FormatLevelChars = FormatLevelChars::const_default();
+impl From<FormatLevelChars> for Cow<'static, FormatLevelChars> {
+    fn from(chars: FormatLevelChars) -> Self {
+        Cow::Owned(chars)
+    }
+}
 
-pub(crate) struct FmtLevel {
-    pub level: Level,
-    pub chars: &'static FormatLevelChars,
-    #[cfg(feature = "ansi")]
-    pub ansi: bool,
+impl From<&'static FormatLevelChars> for Cow<'static, FormatLevelChars> {
+    fn from(chars: &'static FormatLevelChars) -> Self {
+        Cow::Borrowed(chars)
+    }
 }
 
-impl FmtLevel {
+pub(crate) static DEFAULT_FORMAT_LEVEL_CHARS: FormatLevelChars = FormatLevelChars::const_default();
+
+pub(crate) struct FmtLevel<'a> {
+    pub label: &'a str,
+    pub style: Style,
+}
+
+impl<'a> FmtLevel<'a> {
     pub(crate) fn format_level(
         level: Level,
-        chars: &'static FormatLevelChars,
-        ansi: bool,
-    ) -> FmtLevel {
-        #[cfg(not(feature = "ansi"))]
-        let _ = ansi;
+        chars: &'a FormatLevelChars,
+        theme: &Theme,
+    ) -> FmtLevel<'a> {
         FmtLevel {
-            level,
-            chars,
-            #[cfg(feature = "ansi")]
-            ansi,
+            label: chars.label(level),
+            style: chars.styles[index(level)].unwrap_or_else(|| theme.level(level)),
+        }
+    }
+}
+
+impl<'a> fmt::Display for FmtLevel<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = self.label.trim_end();
+        write!(f, "{}", self.style.prefix())?;
+        f.pad(label)?;
+        write!(f, "{}", self.style.suffix())?;
+        f.write_str(&self.label[label.len()..])
+    }
+}
+
+/// Writes `text`, which may contain ANSI escape codes of its own, in `style`.
+///
+/// A reset inside of `text` would also cancel `style` for the rest of `text`, so
+/// `style` is reapplied after each one.
+pub(crate) struct Styled<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) style: Style,
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+impl<'a> fmt::Display for Styled<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = self.style.prefix().to_string();
+        if prefix.is_empty() {
+            return f.write_str(self.text);
         }
+
+        f.write_str(&prefix)?;
+        let mut parts = self.text.split(ANSI_RESET);
+        f.write_str(parts.next().unwrap_or_default())?;
+        for part in parts {
+            write!(f, "{ANSI_RESET}{prefix}{part}")?;
+        }
+        write!(f, "{}", self.style.suffix())
     }
 }
 
-impl fmt::Display for FmtLevel {
+/// Writes `value` in `style`.
+pub(crate) struct Painted<'a> {
+    pub(crate) value: &'a dyn fmt::Display,
+    pub(crate) style: Style,
+}
+
+impl<'a> fmt::Display for Painted<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let chars = self.chars;
-        #[cfg(feature = "ansi")]
-        if self.ansi {
-            return match self.level {
-                Level::TRACE => write!(f, "{}", Color::Purple.paint(chars.trace)),
-                Level::DEBUG => write!(f, "{}", Color::Blue.paint(chars.debug)),
-                Level::INFO => write!(f, "{}", Color::Green.paint(chars.info)),
-                Level::WARN => write!(f, "{}", Color::Yellow.paint(chars.warn)),
-                Level::ERROR => write!(f, "{}", Color::Red.paint(chars.error)),
+        write!(
+            f,
+            "{}{}{}",
+            self.style.prefix(),
+            self.value,
+            self.style.suffix()
+        )
+    }
+}
+
+/// A writer that reapplies `style` after each ANSI reset written through it, so that
+/// styled elements nested inside of a line colored with `style` don't cancel it.
+///
+/// `style`'s prefix and suffix themselves are left to the caller.
+pub(crate) struct Restyle<'a> {
+    inner: &'a mut dyn fmt::Write,
+    prefix: String,
+}
+
+impl<'a> Restyle<'a> {
+    pub(crate) fn new(inner: &'a mut dyn fmt::Write, style: Style) -> Self {
+        Self {
+            inner,
+            prefix: style.prefix().to_string(),
+        }
+    }
+}
+
+impl<'a> fmt::Write for Restyle<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut parts = s.split(ANSI_RESET);
+        self.inner.write_str(parts.next().unwrap_or_default())?;
+        for part in parts {
+            self.inner.write_str(ANSI_RESET)?;
+            self.inner.write_str(&self.prefix)?;
+            self.inner.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// A writer that counts the number of bytes written through it.
+///
+/// While `space` is set, a space is held back: it is only written (and `space` unset)
+/// once anything else is written, so it is dropped if nothing is.
+pub(crate) struct Counting<'a> {
+    inner: &'a mut dyn fmt::Write,
+    written: &'a Cell<usize>,
+    space: &'a Cell<bool>,
+}
+
+impl<'a> Counting<'a> {
+    pub(crate) fn new(
+        inner: &'a mut dyn fmt::Write,
+        written: &'a Cell<usize>,
+        space: &'a Cell<bool>,
+    ) -> Self {
+        Self {
+            inner,
+            written,
+            space,
+        }
+    }
+}
+
+impl<'a> fmt::Write for Counting<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            return Ok(());
+        }
+        if self.space.replace(false) {
+            self.written.set(self.written.get() + 1);
+            self.inner.write_char(' ')?;
+        }
+        self.written.set(self.written.get() + s.len());
+        self.inner.write_str(s)
+    }
+}
+
+/// A writer that writes at most `max` bytes of text through it. Once finished, it writes
+/// a `...[truncated N bytes]` marker if any text was cut.
+///
+/// Text is only cut between characters. ANSI escape codes don't count towards `max` and
+/// are still written after the cut, so that styles and hyperlinks are closed. If the
+/// text was wrapped in quotes or brackets, the closing one is written after the marker.
+///
+/// A writer created with [`Truncate::per_line`] applies `max` to each line separately,
+/// writing the marker at the end of each line that was cut.
+pub(crate) struct Truncate<'a> {
+    inner: &'a mut dyn fmt::Write,
+    max: usize,
+    per_line: bool,
+    remaining: usize,
+    truncated: usize,
+    escape: EscapeState,
+    first: Option<char>,
+    last: Option<char>,
+}
+
+/// Where a [`Truncate`] is within an ANSI escape code.
+#[derive(Clone, Copy)]
+enum EscapeState {
+    Text,
+    /// After `ESC`.
+    Escape,
+    /// Within a control sequence, such as a style.
+    Csi,
+    /// Within an operating system command, such as a hyperlink.
+    Osc,
+    /// After `ESC` within an operating system command.
+    OscEscape,
+}
+
+impl<'a> Truncate<'a> {
+    pub(crate) fn new(inner: &'a mut dyn fmt::Write, max: usize) -> Self {
+        Self {
+            inner,
+            max,
+            per_line: false,
+            remaining: max,
+            truncated: 0,
+            escape: EscapeState::Text,
+            first: None,
+            last: None,
+        }
+    }
+
+    pub(crate) fn per_line(inner: &'a mut dyn fmt::Write, max: usize) -> Self {
+        Self {
+            per_line: true,
+            ..Self::new(inner, max)
+        }
+    }
+
+    /// Writes the truncation marker, if needed, and returns the inner writer.
+    pub(crate) fn finish(mut self) -> Result<&'a mut dyn fmt::Write, fmt::Error> {
+        self.write_marker()?;
+        Ok(self.inner)
+    }
+
+    /// Writes the truncation marker if any text was cut, and starts over with `max`
+    /// bytes.
+    fn write_marker(&mut self) -> fmt::Result {
+        if self.truncated > 0 {
+            let closing = match (self.first, self.last) {
+                (Some('"'), Some('"')) => Some('"'),
+                (Some('['), Some(']')) => Some(']'),
+                _ => None,
             };
+            let truncated = self.truncated - closing.map_or(0, char::len_utf8);
+            write!(self.inner, "...[truncated {truncated} bytes]")?;
+            if let Some(closing) = closing {
+                self.inner.write_char(closing)?;
+            }
         }
-        match self.level {
-            Level::TRACE => f.pad(chars.trace),
-            Level::DEBUG => f.pad(chars.debug),
-            Level::INFO => f.pad(chars.info),
-            Level::WARN => f.pad(chars.warn),
-            Level::ERROR => f.pad(chars.error),
+        self.remaining = self.max;
+        self.truncated = 0;
+        self.first = None;
+        self.last = None;
+        Ok(())
+    }
+
+    /// Returns whether or not `c` is written.
+    fn keep(&mut self, c: char) -> bool {
+        self.escape = match (self.escape, c) {
+            (EscapeState::Text, '\x1b') => EscapeState::Escape,
+            (EscapeState::Text, c) => {
+                self.first.get_or_insert(c);
+                self.last = Some(c);
+                if self.truncated == 0 && c.len_utf8() <= self.remaining {
+                    self.remaining -= c.len_utf8();
+                    return true;
+                }
+                self.truncated += c.len_utf8();
+                return false;
+            }
+            (EscapeState::Escape, '[') => EscapeState::Csi,
+            (EscapeState::Escape, ']') => EscapeState::Osc,
+            (EscapeState::Escape, _) => EscapeState::Text,
+            (EscapeState::Csi, '\x40'..='\x7e') => EscapeState::Text,
+            (EscapeState::Csi, _) => EscapeState::Csi,
+            (EscapeState::Osc, '\x07') => EscapeState::Text,
+            (EscapeState::Osc, '\x1b') => EscapeState::OscEscape,
+            (EscapeState::Osc, _) => EscapeState::Osc,
+            (EscapeState::OscEscape, '\\') => EscapeState::Text,
+            (EscapeState::OscEscape, _) => EscapeState::Osc,
+        };
+        true
+    }
+}
+
+impl<'a> fmt::Write for Truncate<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (idx, c) in s.char_indices() {
+            if self.per_line && c == '\n' && matches!(self.escape, EscapeState::Text) {
+                self.inner.write_str(&s[start..idx])?;
+                self.write_marker()?;
+                start = idx;
+            } else if !self.keep(c) {
+                self.inner.write_str(&s[start..idx])?;
+                start = idx + c.len_utf8();
+            }
         }
+        self.inner.write_str(&s[start..])
+    }
+}
+
+/// Formats `value`, truncated to `max` bytes with a [`Truncate`] if `max` is set.
+pub(crate) struct Truncated<'a> {
+    pub(crate) value: &'a dyn fmt::Debug,
+    pub(crate) max: Option<usize>,
+}
+
+impl<'a> fmt::Debug for Truncated<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+
+        let max = match self.max {
+            Some(max) => max,
+            None => return self.value.fmt(f),
+        };
+        let mut truncate = Truncate::new(f, max);
+        write!(truncate, "{:?}", self.value)?;
+        truncate.finish().map(|_| ())
     }
 }
 
@@ -95,15 +464,6 @@ pub struct UtcTime {
 
 impl FormatTime for UtcTime {
     fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
-        #[cfg(feature = "ansi")]
-        if w.has_ansi_escapes() {
-            let style = Style::new().dimmed();
-            write!(w, "{}", style.prefix())?;
-            self.time.format_time(w)?;
-            write!(w, "{}", style.suffix())?;
-            return Ok(());
-        }
-
         self.time.format_time(w)
     }
 }
@@ -130,15 +490,6 @@ pub struct LocalTime {
 
 impl FormatTime for LocalTime {
     fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
-        #[cfg(feature = "ansi")]
-        if w.has_ansi_escapes() {
-            let style = Style::new().dimmed();
-            write!(w, "{}", style.prefix())?;
-            self.time.format_time(w)?;
-            write!(w, "{}", style.suffix())?;
-            return Ok(());
-        }
-
         self.time.format_time(w)
     }
 }
@@ -152,62 +503,377 @@ impl Default for LocalTime {
     }
 }
 
-pub(crate) struct FormatProcessData<'a> {
-    pub(crate) pid: u32,
-    pub(crate) thread_name: Option<&'a str>,
-    pub(crate) with_thread_names: bool,
+/// How the source file of an event is printed.
+///
+/// Defaults to [`FilePath::Full`].
+#[derive(Clone, Debug, Default)]
+pub enum FilePath {
+    /// The path as recorded in the event's [`Metadata`], such as
+    /// `src/main.rs` or `/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.21.0/src/runtime/mod.rs`.
+    #[default]
+    Full,
+    /// Only the file's name, such as `mod.rs`. This matches glog.
+    Basename,
+    /// The path with the first of the given prefixes that it starts with removed.
+    ///
+    /// Paths that don't start with any of the prefixes are printed in full.
+    StripPrefixes(Vec<String>),
+    /// Paths into Cargo's registry are shortened to `crate@version/path`, such as
+    /// `tokio@1.21.0/src/runtime/mod.rs`. Other paths are printed in full.
+    ShortenRegistry,
+}
+
+const PATH_SEPARATORS: [char; 2] = ['/', '\\'];
+
+impl FilePath {
+    pub(crate) fn display<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        match self {
+            FilePath::Full => Cow::Borrowed(path),
+            FilePath::Basename => {
+                Cow::Borrowed(path.rsplit(PATH_SEPARATORS).next().unwrap_or(path))
+            }
+            FilePath::StripPrefixes(prefixes) => Cow::Borrowed(
+                prefixes
+                    .iter()
+                    .find_map(|prefix| path.strip_prefix(prefix.as_str()))
+                    .map(|path| path.trim_start_matches(PATH_SEPARATORS))
+                    .unwrap_or(path),
+            ),
+            FilePath::ShortenRegistry => shorten_registry_path(path)
+                .map(Cow::Owned)
+                .unwrap_or(Cow::Borrowed(path)),
+        }
+    }
+}
+
+/// Shortens `.../registry/src/<index>/<crate>-<version>/<path>` to
+/// `<crate>@<version>/<path>`.
+fn shorten_registry_path(path: &str) -> Option<String> {
+    let start = path
+        .find("registry/src/")
+        .or_else(|| path.find("registry\\src\\"))?;
+    let mut components = path[start + "registry/src/".len()..].splitn(3, PATH_SEPARATORS);
+    let _index = components.next()?;
+    let krate = components.next()?;
+    let rest = components.next()?;
+
+    // Crate names may contain `-`, but versions always start with a digit.
+    let split = krate
+        .char_indices()
+        .find(|&(i, c)| c == '-' && krate[i + 1..].starts_with(|c: char| c.is_ascii_digit()))?
+        .0;
+    let (name, version) = (&krate[..split], &krate[split + 1..]);
+    Some(format!("{name}@{version}/{rest}"))
+}
+
+/// The source location of an event: `file:line`, or the module path if the
+/// event has no file.
+pub(crate) struct FormatLocation<'a> {
     pub(crate) metadata: &'a Metadata<'a>,
-    pub(crate) with_target: bool,
-    #[cfg(feature = "ansi")]
-    pub(crate) ansi: bool,
+    pub(crate) file_path: &'a FilePath,
 }
 
-impl<'a> fmt::Display for FormatProcessData<'a> {
+impl<'a> fmt::Display for FormatLocation<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let thread_name = self.thread_name;
-        let target = self.metadata.target();
-        let file = self.metadata.file().unwrap_or("");
-        let line = match self.metadata.line() {
-            Some(line) => format!("{line}"),
-            None => String::new(),
+        let file = match self.metadata.file() {
+            Some(file) => self.file_path.display(file),
+            // some events (e.g. from `log` records) don't have a file.
+            None => {
+                return f.write_str(
+                    self.metadata
+                        .module_path()
+                        .unwrap_or_else(|| self.metadata.target()),
+                )
+            }
         };
-        // write the always unstyled PID
-        write!(f, " {pid:>5}", pid = self.pid)?;
-
-        #[cfg(feature = "ansi")]
-        if self.ansi {
-            let style = Style::new().bold();
-            // start by bolding all the expected data
-            write!(f, "{}", style.prefix())?;
-            if let Some(name) = thread_name {
-                if self.with_thread_names {
-                    write!(f, " {name}")?
+        match self.metadata.line() {
+            Some(line) => write!(f, "{file}:{line}"),
+            None => write!(f, "{file}"),
+        }
+    }
+}
+
+/// How messages and fields that span several lines are printed.
+///
+/// Defaults to [`Multiline::Keep`].
+///
+/// With [`Multiline::Escape`] and [`Multiline::RepeatPrefix`], newlines are handled
+/// here regardless of [`GlogFields::with_escaping`], which still applies to other
+/// control characters. With [`Multiline::Keep`], newlines are left to the escaping
+/// mode instead.
+///
+/// [`GlogFields::with_escaping`]: crate::GlogFields::with_escaping
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Multiline {
+    /// Newlines are left to [`GlogFields::with_escaping`]: when it escapes control
+    /// characters, which [`Escaping::Auto`] does when ANSI escape codes are disabled,
+    /// newlines are escaped as `\n` as well, so that a value can't forge a line.
+    /// Otherwise, lines are printed as they are, so only the first line has a prefix.
+    ///
+    /// [`GlogFields::with_escaping`]: crate::GlogFields::with_escaping
+    /// [`Escaping::Auto`]: crate::Escaping::Auto
+    #[default]
+    Keep,
+    /// Newlines and carriage returns are escaped as `\n` and `\r`, so that every
+    /// event is printed on a single line.
+    Escape,
+    /// Each continuation line repeats the prefix of the first line, followed by
+    /// `[...]` in place of the span context, as glog does for multi-line messages.
+    RepeatPrefix,
+}
+
+/// The marker printed in place of the span context on continuation lines.
+const CONTINUATION: &str = "[...] ";
+
+/// Writes a line whose `body` may span several lines, according to `multiline`.
+pub(crate) struct Continued<'a> {
+    pub(crate) head: &'a str,
+    pub(crate) body: &'a str,
+    pub(crate) multiline: Multiline,
+    /// Whether ANSI escape codes are enabled, in which case styles are reset at the end
+    /// of each line so that they don't carry over to the next line's prefix.
+    pub(crate) ansi: bool,
+}
+
+impl<'a> fmt::Display for Continued<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.head)?;
+        match self.multiline {
+            Multiline::Keep => f.write_str(self.body),
+            Multiline::Escape => {
+                let mut start = 0;
+                for (idx, c) in self.body.match_indices(['\n', '\r']) {
+                    f.write_str(&self.body[start..idx])?;
+                    f.write_str(if c == "\n" { "\\n" } else { "\\r" })?;
+                    start = idx + 1;
                 }
+                f.write_str(&self.body[start..])
             }
-
-            if self.with_target {
-                write!(f, " [{target}]")?;
+            Multiline::RepeatPrefix => {
+                let mut lines = self.body.split('\n').peekable();
+                f.write_str(lines.next().unwrap_or_default().trim_end_matches('\r'))?;
+                while let Some(line) = lines.next() {
+                    // a trailing newline, possibly followed by nothing but whitespace and
+                    // escape codes, doesn't start a new line.
+                    if lines.peek().is_none() && is_blank(line) {
+                        return f.write_str(line);
+                    }
+                    if self.ansi {
+                        f.write_str(ANSI_RESET)?;
+                    }
+                    write!(
+                        f,
+                        "\n{}{CONTINUATION}{}",
+                        self.head,
+                        line.trim_end_matches('\r')
+                    )?;
+                }
+                Ok(())
             }
+        }
+    }
+}
+
+/// Returns whether or not `text` only contains whitespace and ANSI escape codes.
+fn is_blank(text: &str) -> bool {
+    let mut rest = text.trim_start();
+    while let Some(escape) = rest.strip_prefix("\x1b[") {
+        match escape.find('m') {
+            Some(end) => rest = escape[end + 1..].trim_start(),
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// The tokio task that an event was recorded in.
+#[cfg(feature = "tokio")]
+pub(crate) struct TokioTask {
+    pub(crate) id: tokio::task::Id,
+    pub(crate) name: Option<String>,
+}
+
+#[cfg(feature = "tokio")]
+impl fmt::Display for TokioTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task:{}", self.id)?;
+        if let Some(name) = &self.name {
+            write!(f, "({name})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The width of a column in the prefix, such as the thread name or the target.
+///
+/// Defaults to [`ColumnWidth::Natural`].
+#[derive(Clone, Debug, Default)]
+pub enum ColumnWidth {
+    /// Values are printed as-is.
+    #[default]
+    Natural,
+    /// Values are padded to at least the given number of characters.
+    Min(usize),
+    /// Values are padded or truncated to exactly the given number of characters.
+    ///
+    /// Thread names are truncated at the end. Targets and source locations are
+    /// truncated at the start, keeping their most specific part. A width of 0 hides the
+    /// column, along with the space separating it from the previous one.
+    Fixed(usize),
+    /// Values are padded to the widest value printed so far.
+    Adaptive,
+}
+
+/// A column of the prefix and, for [`ColumnWidth::Adaptive`], the widest value printed in it.
+#[derive(Debug, Default)]
+pub(crate) struct Column {
+    width: ColumnWidth,
+    widest: AtomicUsize,
+}
 
-            write!(f, " {file}:{line}")?;
+impl Column {
+    pub(crate) fn new(width: ColumnWidth) -> Self {
+        Column {
+            width,
+            widest: AtomicUsize::new(0),
+        }
+    }
 
-            // end bolding
-            write!(f, "{}", style.suffix())?;
+    /// Returns true if this column is always empty, because its width is fixed to 0.
+    pub(crate) fn is_hidden(&self) -> bool {
+        matches!(self.width, ColumnWidth::Fixed(0))
+    }
 
+    /// Writes `value` in `style`, padded or truncated to this column's width.
+    ///
+    /// Padding goes on the left when `align_right` is set, and on the right otherwise.
+    /// If there is a `link`, `value` is made a hyperlink to it.
+    pub(crate) fn write(
+        &self,
+        f: &mut dyn fmt::Write,
+        value: &dyn fmt::Display,
+        style: Style,
+        align_right: bool,
+        truncate_start: bool,
+        link: Option<&str>,
+    ) -> fmt::Result {
+        let styled = |value| Hyperlink {
+            url: link,
+            text: Painted { value, style },
+        };
+        if self.is_hidden() {
             return Ok(());
         }
-        if let Some(name) = thread_name {
-            if self.with_thread_names {
-                write!(f, " {name}")?
+        let (width, truncate) = match self.width {
+            ColumnWidth::Natural => return write!(f, "{}", styled(value)),
+            ColumnWidth::Min(width) => (width, false),
+            ColumnWidth::Fixed(width) => (width, true),
+            ColumnWidth::Adaptive => (0, false),
+        };
+
+        let value = value.to_string();
+        let len = value.chars().count();
+        let width = match self.width {
+            ColumnWidth::Adaptive => self.widest.fetch_max(len, Ordering::Relaxed).max(len),
+            _ => width,
+        };
+
+        let value = match (truncate && len > width, truncate_start) {
+            (false, _) => value.as_str(),
+            (true, true) => {
+                let start = value
+                    .char_indices()
+                    .nth(len - width)
+                    .map_or(value.len(), |(start, _)| start);
+                &value[start..]
+            }
+            (true, false) => {
+                let end = value
+                    .char_indices()
+                    .nth(width)
+                    .map(|(end, _)| end)
+                    .unwrap_or(value.len());
+                &value[..end]
+            }
+        };
+        // pad outside of the style, so that e.g. underlines don't extend into the padding.
+        let padding = width.saturating_sub(value.chars().count());
+        if align_right {
+            write!(f, "{:padding$}", "")?;
+        }
+        write!(f, "{}", styled(&value))?;
+        if !align_right {
+            write!(f, "{:padding$}", "")?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct FormatProcessData<'a> {
+    pub(crate) pid: u32,
+    pub(crate) thread_name: Option<&'a str>,
+    pub(crate) with_thread_names: bool,
+    pub(crate) thread_name_column: &'a Column,
+    #[cfg(feature = "tokio")]
+    pub(crate) task: Option<TokioTask>,
+    pub(crate) metadata: &'a Metadata<'a>,
+    pub(crate) file_path: &'a FilePath,
+    pub(crate) location_column: &'a Column,
+    pub(crate) with_target: bool,
+    pub(crate) target_column: &'a Column,
+    pub(crate) hyperlinks: Option<&'a Hyperlinks>,
+    pub(crate) theme: &'a Theme,
+}
+
+impl<'a> FormatProcessData<'a> {
+    /// Returns the URL that the source location links to, if any.
+    pub(crate) fn location_url(&self) -> Option<String> {
+        let hyperlinks = self.hyperlinks?;
+        let file = self.metadata.file()?;
+        Some(hyperlinks.url(file, self.metadata.line()))
+    }
+}
+
+impl<'a> fmt::Display for FormatProcessData<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let theme = self.theme;
+        let pid = theme.pid;
+        write!(f, " {}{:>5}{}", pid.prefix(), self.pid, pid.suffix())?;
+
+        if let Some(name) = self.thread_name {
+            if self.with_thread_names && !self.thread_name_column.is_hidden() {
+                write!(f, " ")?;
+                let style = theme.thread_name_style(name);
+                self.thread_name_column
+                    .write(f, &name, style, false, false, None)?;
             }
         }
 
-        if self.with_target {
-            write!(f, " [{target}]")?;
+        #[cfg(feature = "tokio")]
+        if let Some(task) = &self.task {
+            let style = theme.task_style(&task.id);
+            write!(f, " {}{}{}", style.prefix(), task, style.suffix())?;
         }
 
-        write!(f, " {file}:{line}")?;
-        Ok(())
+        if self.with_target && !self.target_column.is_hidden() {
+            let target = format_args!("[{}]", self.metadata.target());
+            write!(f, " ")?;
+            self.target_column
+                .write(f, &target, theme.target, false, true, None)?;
+        }
+
+        if self.location_column.is_hidden() {
+            return Ok(());
+        }
+        // the location is right-aligned so that the `]` closing the prefix lines up.
+        let url = self.location_url();
+        let location = FormatLocation {
+            metadata: self.metadata,
+            file_path: self.file_path,
+        };
+        write!(f, " ")?;
+        self.location_column
+            .write(f, &location, theme.location, true, true, url.as_deref())
     }
 }
 
@@ -215,8 +881,7 @@ impl<'a> fmt::Display for FormatProcessData<'a> {
 pub(crate) struct FormatSpanFields<'a> {
     span_name: &'static str,
     fields: Option<&'a str>,
-    #[cfg(feature = "ansi")]
-    pub ansi: bool,
+    theme: &'a Theme,
     print_span_names: bool,
 }
 
@@ -224,16 +889,13 @@ impl<'a> FormatSpanFields<'a> {
     pub(crate) fn format_fields(
         span_name: &'static str,
         fields: Option<&'a str>,
-        ansi: bool,
+        theme: &'a Theme,
         print_span_names: bool,
     ) -> Self {
-        #[cfg(not(feature = "ansi"))]
-        let _ = ansi;
         Self {
             span_name,
             fields,
-            #[cfg(feature = "ansi")]
-            ansi,
+            theme,
             print_span_names,
         }
     }
@@ -241,29 +903,16 @@ impl<'a> FormatSpanFields<'a> {
 
 impl<'a> fmt::Display for FormatSpanFields<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        #[cfg(feature = "ansi")]
-        if self.ansi {
-            let bold = Style::new().bold();
-
-            if self.print_span_names {
-                write!(f, "{}", bold.paint(self.span_name))?;
-            }
-
-            let italic = Style::new().italic();
-            if let Some(fields) = self.fields {
-                if self.print_span_names {
-                    write!(f, "{{{}}}", italic.paint(fields))?;
-                } else {
-                    write!(f, "{}", italic.paint(fields))?;
-                }
-            };
-            return Ok(());
-        }
-
         if self.print_span_names {
-            write!(f, "{}", self.span_name)?;
+            let style = self.theme.span_name_style(self.span_name);
+            write!(f, "{}{}{}", style.prefix(), self.span_name, style.suffix())?;
         }
+
         if let Some(fields) = self.fields {
+            let fields = Styled {
+                text: fields,
+                style: self.theme.span_fields,
+            };
             if self.print_span_names {
                 write!(f, "{{{fields}}}")?;
             } else {
@@ -274,3 +923,103 @@ impl<'a> fmt::Display for FormatSpanFields<'a> {
         Ok(())
     }
 }
+
+/// A span lifecycle event synthesized by [`fmt::Layer`] when span events are enabled.
+///
+/// [`fmt::Layer`]: tracing_subscriber::fmt::Layer
+pub(crate) struct SpanLifecycle {
+    kind: FmtSpan,
+    label: &'static str,
+    busy: Option<String>,
+    idle: Option<String>,
+}
+
+impl SpanLifecycle {
+    /// Returns the lifecycle event that `event` represents, if any.
+    ///
+    /// `fmt::Layer` reuses the span's metadata for these events, which is how they are
+    /// distinguished from events that were recorded by the user.
+    pub(crate) fn from_event(event: &Event<'_>) -> Option<Self> {
+        if !event.metadata().is_span() {
+            return None;
+        }
+
+        let mut visitor = LifecycleVisitor::default();
+        event.record(&mut visitor);
+        let (kind, label) = match visitor.message.as_deref()? {
+            "new" => (FmtSpan::NEW, "new"),
+            "enter" => (FmtSpan::ENTER, "enter"),
+            "exit" => (FmtSpan::EXIT, "exit"),
+            "close" => (FmtSpan::CLOSE, "close"),
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            label,
+            busy: visitor.busy,
+            idle: visitor.idle,
+        })
+    }
+
+    pub(crate) fn kind(&self) -> &FmtSpan {
+        &self.kind
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns whether or not the event records how long the span was busy and idle.
+    pub(crate) fn has_timings(&self) -> bool {
+        self.busy.is_some() && self.idle.is_some()
+    }
+
+    /// Calls `f` with a copy of the lifecycle event that only has its timings (e.g.
+    /// `time.busy`) as fields, so that the message (e.g. `close`) can be printed
+    /// separately.
+    pub(crate) fn with_event<R>(&self, event: &Event<'_>, f: impl FnOnce(&Event<'_>) -> R) -> R {
+        let meta = event.metadata();
+        match (&self.busy, &self.idle) {
+            (Some(busy), Some(idle)) => {
+                let fields = FieldSet::new(&["time.busy", "time.idle"], meta.callsite());
+                let mut iter = fields.iter();
+                let (busy, idle) = (display(busy), display(idle));
+                let values = [
+                    (&iter.next().unwrap(), Some(&busy as &dyn Value)),
+                    (&iter.next().unwrap(), Some(&idle as &dyn Value)),
+                ];
+                let values = fields.value_set(&values);
+                f(&Event::new_child_of(event.parent().cloned(), meta, &values))
+            }
+            _ => {
+                let fields = FieldSet::new(&[], meta.callsite());
+                let values: [(&Field, Option<&dyn Value>); 0] = [];
+                let values = fields.value_set(&values);
+                f(&Event::new_child_of(event.parent().cloned(), meta, &values))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct LifecycleVisitor {
+    message: Option<String>,
+    busy: Option<String>,
+    idle: Option<String>,
+}
+
+impl Visit for LifecycleVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{value}"))
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = Some(format!("{value:?}"));
+        match field.name() {
+            "message" => self.message = value,
+            "time.busy" => self.busy = value,
+            "time.idle" => self.idle = value,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::layout::{parse_template, TemplateSegment};
+use std::{
+    env,
+    error::Error,
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Makes the source location (`file:line`) of each event an [OSC 8] hyperlink, which
+/// many terminals can open with a click.
+///
+/// Links point to a URL built from a template, in which the following placeholders
+/// are replaced:
+///
+/// - `{abs_path}`: the absolute path of the source file, starting with a `/`, even on
+///   Windows. The `/` isn't repeated when the template ends with one, as in
+///   `vscode://file/{abs_path}`, other than the `//` following the URL's scheme. Paths
+///   recorded relative to the workspace, as Cargo does for the crates in a workspace,
+///   are resolved against the workspace root.
+/// - `{rel_path}`: the path of the source file relative to the workspace root, or its
+///   absolute path if it is outside of the workspace.
+/// - `{path}`: the path of the source file as recorded in the event's metadata.
+/// - `{line}`: the line in the source file.
+///
+/// Paths always use `/` as their separator and are percent-encoded as needed.
+///
+/// ```
+/// use tracing_glog::{Glog, Hyperlinks};
+///
+/// let hyperlinks = Hyperlinks::new("https://github.com/org/repo/blob/4f2e1c9/{rel_path}#L{line}")
+///     .expect("invalid URL template")
+///     .with_workspace_root("/home/user/repo");
+/// let glog = Glog::default().with_hyperlinks(hyperlinks);
+/// ```
+///
+/// Hyperlinks are escape codes, so they are only written when ANSI escape codes are
+/// enabled.
+///
+/// [OSC 8]: https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda
+#[derive(Clone, Debug)]
+pub struct Hyperlinks {
+    url: Vec<UrlSegment>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+enum UrlSegment {
+    Literal(String),
+    AbsPath,
+    RelPath,
+    Path,
+    Line,
+}
+
+impl TemplateSegment for UrlSegment {
+    fn literal(text: String) -> Self {
+        UrlSegment::Literal(text)
+    }
+
+    fn placeholder(name: &str) -> Result<Self, String> {
+        match name {
+            "abs_path" => Ok(UrlSegment::AbsPath),
+            "rel_path" => Ok(UrlSegment::RelPath),
+            "path" => Ok(UrlSegment::Path),
+            "line" => Ok(UrlSegment::Line),
+            other => Err(format!(
+                "unknown placeholder `{{{other}}}`, expected one of `abs_path`, `rel_path`, \
+                 `path` and `line`"
+            )),
+        }
+    }
+}
+
+impl Hyperlinks {
+    /// Creates hyperlinks to URLs built from `url_template`.
+    ///
+    /// The workspace root defaults to the current directory.
+    pub fn new(url_template: &str) -> Result<Hyperlinks, ParseHyperlinksError> {
+        let url = parse_template(url_template)
+            .map_err(|(offset, message)| ParseHyperlinksError { offset, message })?;
+        Ok(Hyperlinks {
+            url,
+            workspace_root: env::current_dir().unwrap_or_default(),
+        })
+    }
+
+    /// Links to the source file with a `file://{abs_path}` URL.
+    pub fn file() -> Hyperlinks {
+        Hyperlinks::new("file://{abs_path}").expect("valid URL template")
+    }
+
+    /// Opens the source file at the event's line in Visual Studio Code, with a
+    /// `vscode://file/{abs_path}:{line}` URL.
+    pub fn vscode() -> Hyperlinks {
+        Hyperlinks::new("vscode://file/{abs_path}:{line}").expect("valid URL template")
+    }
+
+    /// Sets the directory that relative source file paths are resolved against.
+    pub fn with_workspace_root(self, workspace_root: impl Into<PathBuf>) -> Hyperlinks {
+        Hyperlinks {
+            workspace_root: workspace_root.into(),
+            ..self
+        }
+    }
+
+    /// Returns the URL for `line` in `file`.
+    pub(crate) fn url(&self, file: &str, line: Option<u32>) -> String {
+        let path = Path::new(file);
+        let mut url = String::new();
+        for segment in &self.url {
+            match segment {
+                UrlSegment::Literal(text) => url.push_str(text),
+                UrlSegment::AbsPath => {
+                    let abs_path = self.workspace_root.join(path);
+                    let abs_path = abs_path.to_string_lossy();
+                    if !url.ends_with('/') || url.ends_with("//") {
+                        url.push('/');
+                    }
+                    push_encoded(&mut url, abs_path.trim_start_matches(['/', '\\']));
+                }
+                UrlSegment::RelPath => {
+                    let rel_path = path.strip_prefix(&self.workspace_root).unwrap_or(path);
+                    push_encoded(&mut url, &rel_path.to_string_lossy());
+                }
+                UrlSegment::Path => push_encoded(&mut url, file),
+                UrlSegment::Line => {
+                    if let Some(line) = line {
+                        let _ = write!(url, "{line}");
+                    }
+                }
+            }
+        }
+        url
+    }
+}
+
+/// Appends `path` to `url`, using `/` as the separator and percent-encoding any
+/// characters that aren't allowed in the path of a URL.
+fn push_encoded(url: &mut String, path: &str) {
+    for byte in path.bytes() {
+        match byte {
+            b'\\' => url.push('/'),
+            b'a'..=b'z'
+            | b'A'..=b'Z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~'
+            | b'/'
+            | b':'
+            | b'@'
+            | b'!'
+            | b'$'
+            | b'&'
+            | b'\''
+            | b'('
+            | b')'
+            | b'*'
+            | b'+'
+            | b','
+            | b';'
+            | b'=' => url.push(byte as char),
+            byte => {
+                let _ = write!(url, "%{byte:02X}");
+            }
+        }
+    }
+}
+
+/// Writes `text` as an OSC 8 hyperlink to `url`.
+pub(crate) struct Hyperlink<'a, D> {
+    pub(crate) url: Option<&'a str>,
+    pub(crate) text: D,
+}
+
+impl<'a, D: fmt::Display> fmt::Display for Hyperlink<'a, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.url {
+            Some(url) => write!(f, "\x1b]8;;{url}\x1b\\{}\x1b]8;;\x1b\\", self.text),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// An error returned when parsing the URL template of [`Hyperlinks`] fails.
+#[derive(Debug)]
+pub struct ParseHyperlinksError {
+    offset: usize,
+    message: String,
+}
+
+impl ParseHyperlinksError {
+    /// Returns the byte offset in the template at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseHyperlinksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid URL template at offset {}: {}",
+            self.offset, self.message
+        )
+    }
+}
+
+impl Error for ParseHyperlinksError {}
@@ -0,0 +1,225 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// The layout of an entire line, parsed from a template such as:
+///
+/// ```text
+/// {level}{time} {tid} {thread} [{target}] {file}:{line}] {spans} {message} {fields}
+/// ```
+///
+/// Templates are parsed once, when the layout is created, and unknown placeholders are
+/// rejected then, so a layout can be loaded from configuration and installed with
+/// [`Glog::with_layout`]:
+///
+/// ```
+/// use tracing_glog::{Glog, GlogFields, Layout};
+///
+/// let layout: Layout = "{level}{time} {thread} {location}] {spans} {message} {fields}"
+///     .parse()
+///     .expect("invalid layout");
+/// tracing_subscriber::fmt()
+///     .event_format(Glog::default().with_layout(layout))
+///     .fmt_fields(GlogFields::default())
+///     .init();
+/// ```
+///
+/// The placeholders are:
+///
+/// - `{level}`: the level's label, see [`Glog::with_format_level_chars`].
+/// - `{time}`: the timestamp, see [`Glog::with_timer`].
+/// - `{pid}`: the ID of the process.
+/// - `{tid}`: the ID of the thread. On Linux and Android, this is the ID assigned by the
+///   kernel, as shown by `top -H` and debuggers. Elsewhere, it's the ID assigned by the
+///   Rust standard library.
+/// - `{thread}`: the name of the thread.
+/// - `{task}`: the ID and name of the tokio task, see [`Glog::with_tokio_tasks`]. This
+///   requires the `tokio` feature.
+/// - `{target}`: the event's target.
+/// - `{file}`: the source file, see [`Glog::with_file_path`].
+/// - `{line}`: the line in the source file.
+/// - `{location}`: `{file}:{line}`, or the module path for events without a file.
+/// - `{promoted}`: the span fields promoted into the prefix, see
+///   [`Glog::with_promoted_fields`].
+/// - `{spans}`: the span context, enclosed in `[]`.
+/// - `{message}`: the event's message.
+/// - `{fields}`: the event's fields other than the message.
+///
+/// `{message}` and `{fields}` can only be told apart when fields are formatted by
+/// [`GlogFields`]. With other field formatters, `{message}` prints all of the fields
+/// and `{fields}` prints nothing.
+///
+/// When a placeholder prints nothing, such as `{spans}` for an event outside of any
+/// span, a single space following it is dropped, so that optional elements don't
+/// leave gaps in the line. A space before it is dropped too if nothing follows it, so
+/// that lines don't end in a space. Literal braces are written as `{{` and `}}`.
+///
+/// [`Glog::with_layout`]: crate::Glog::with_layout
+/// [`Glog::with_format_level_chars`]: crate::Glog::with_format_level_chars
+/// [`Glog::with_timer`]: crate::Glog::with_timer
+/// [`Glog::with_tokio_tasks`]: crate::Glog::with_tokio_tasks
+/// [`Glog::with_file_path`]: crate::Glog::with_file_path
+/// [`Glog::with_promoted_fields`]: crate::Glog::with_promoted_fields
+/// [`GlogFields`]: crate::GlogFields
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub(crate) segments: Vec<Segment>,
+}
+
+/// A piece of a [`Layout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Segment {
+    Literal(String),
+    Level,
+    Time,
+    Pid,
+    Tid,
+    Thread,
+    #[cfg(feature = "tokio")]
+    Task,
+    Target,
+    File,
+    Line,
+    Location,
+    Promoted,
+    Spans,
+    Message,
+    Fields,
+}
+
+const PLACEHOLDERS: &str = "`level`, `time`, `pid`, `tid`, `thread`, `task`, `target`, `file`, \
+                            `line`, `location`, `promoted`, `spans`, `message` and `fields`";
+
+impl TemplateSegment for Segment {
+    fn literal(text: String) -> Self {
+        Segment::Literal(text)
+    }
+
+    fn placeholder(name: &str) -> Result<Segment, String> {
+        let segment = match name {
+            "level" => Segment::Level,
+            "time" => Segment::Time,
+            "pid" => Segment::Pid,
+            "tid" => Segment::Tid,
+            "thread" => Segment::Thread,
+            #[cfg(feature = "tokio")]
+            "task" => Segment::Task,
+            #[cfg(not(feature = "tokio"))]
+            "task" => {
+                return Err("the `{task}` placeholder requires the `tokio` feature".to_string())
+            }
+            "target" => Segment::Target,
+            "file" => Segment::File,
+            "line" => Segment::Line,
+            "location" => Segment::Location,
+            "promoted" => Segment::Promoted,
+            "spans" => Segment::Spans,
+            "message" => Segment::Message,
+            "fields" => Segment::Fields,
+            other => {
+                return Err(format!(
+                    "unknown placeholder `{{{other}}}`, expected one of {PLACEHOLDERS}"
+                ))
+            }
+        };
+        Ok(segment)
+    }
+}
+
+impl Layout {
+    /// Parses a layout from a template. This is equivalent to `template.parse()`.
+    pub fn parse(template: &str) -> Result<Layout, ParseLayoutError> {
+        let segments = parse_template(template)
+            .map_err(|(offset, message)| ParseLayoutError { offset, message })?;
+        Ok(Layout { segments })
+    }
+}
+
+/// A piece of a template with `{placeholder}`s, such as a [`Layout`].
+pub(crate) trait TemplateSegment: Sized {
+    fn literal(text: String) -> Self;
+
+    /// Returns the placeholder called `name`, or a description of why there is none.
+    fn placeholder(name: &str) -> Result<Self, String>;
+}
+
+/// Parses a template into segments, returning the offset and description of the first
+/// error, if any. Literal braces are written as `{{` and `}}`.
+pub(crate) fn parse_template<T: TemplateSegment>(
+    template: &str,
+) -> Result<Vec<T>, (usize, String)> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let rest = &template[offset + 1..];
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| (offset, "unclosed `{`".to_string()))?;
+                let name = &rest[..end];
+                let segment = T::placeholder(name.trim()).map_err(|message| (offset, message))?;
+                if !literal.is_empty() {
+                    segments.push(T::literal(std::mem::take(&mut literal)));
+                }
+                segments.push(segment);
+                // skip the name and the closing brace
+                for _ in 0..=name.chars().count() {
+                    chars.next();
+                }
+            }
+            '}' => {
+                return Err((
+                    offset,
+                    "unmatched `}`, use `}}` for a literal brace".to_string(),
+                ))
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(T::literal(literal));
+    }
+    Ok(segments)
+}
+
+impl FromStr for Layout {
+    type Err = ParseLayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layout::parse(s)
+    }
+}
+
+/// An error returned when parsing a [`Layout`] fails.
+#[derive(Debug)]
+pub struct ParseLayoutError {
+    offset: usize,
+    message: String,
+}
+
+impl ParseLayoutError {
+    /// Returns the byte offset in the template at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid layout at offset {}: {}",
+            self.offset, self.message
+        )
+    }
+}
+
+impl Error for ParseLayoutError {}
@@ -73,6 +73,18 @@
 //! tracing::subscriber::set_global_default(subscriber).expect("Unable to set global subscriber");
 //! ```
 //!
+//! With colors only when writing to a terminal, see [`ColorChoice`]:
+//!
+//! ```
+//! use tracing_glog::{ColorChoice, Glog, GlogFields};
+//!
+//! tracing_subscriber::fmt()
+//!     .with_ansi(ColorChoice::Auto.use_ansi(&std::io::stdout()))
+//!     .event_format(Glog::default())
+//!     .fmt_fields(GlogFields::default())
+//!     .init();
+//! ```
+//!
 //! <div class="example-wrap" style="display:inline-block">
 //! <pre class="compile_fail" style="white-space:normal;font:inherit;">
 //!     <strong>Warning</strong>: The <a href = "https://docs.rs/time/0.3/time/"><code>time</code>
@@ -89,37 +101,46 @@
 //! [`fmt::Subscriber`]: tracing_subscriber::fmt::Subscriber
 //! [`fmt::Layer`]: tracing_subscriber::fmt::Layer
 //! [`Full`]: tracing_subscriber::fmt::format::Full
+#![cfg_attr(tracing_glog_unstable, feature(error_generic_member_access))]
 
 #[deny(rustdoc::broken_intra_doc_links)]
 mod format;
 
+mod hyperlink;
+mod layout;
+
+mod color;
+mod error;
+mod escape;
+
 #[cfg(feature = "time")]
 pub mod time_crate;
 
+mod prefix;
+mod redact;
+mod spans;
+mod theme;
+mod value;
+
+/// A re-export of [`nu_ansi_term`](::nu_ansi_term), for building the styles of a [`Theme`].
 #[cfg(feature = "ansi")]
-mod nu_ansi_term {
+pub mod nu_ansi_term {
     pub use ::nu_ansi_term::*;
 }
 
 #[cfg(not(feature = "ansi"))]
 mod nu_ansi_term {
     // Minimal API shim for nu_ansi_term to avoid a pile of #[cfg(feature = "ansi")] directives.
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, Debug)]
     pub struct Style;
 
     impl Style {
         pub fn new() -> Self {
             Style
         }
-        pub fn bold(&self) -> Self {
-            Style
-        }
         pub fn prefix(&self) -> &'static str {
             ""
         }
-        pub fn infix(&self, _: Style) -> &'static str {
-            ""
-        }
         pub fn suffix(&self) -> &'static str {
             ""
         }
@@ -127,72 +148,252 @@ mod nu_ansi_term {
 }
 
 use crate::nu_ansi_term::Style;
+pub use color::{ColorChoice, ParseColorChoiceError};
+pub use error::ErrorFormat;
+use error::FormatError;
+pub use escape::Escaping;
+use escape::{Escape, Escaped, Quoted};
 use format::FmtLevel;
-pub use format::{FormatLevelChars, LocalTime, UtcTime};
-use std::fmt;
+pub use format::{ColumnWidth, FilePath, FormatLevelChars, LocalTime, Multiline, UtcTime};
+pub use hyperlink::{Hyperlinks, ParseHyperlinksError};
+pub use layout::{Layout, ParseLayoutError};
+pub use prefix::{DefaultPrefix, FormatPrefix, Prefix};
+pub use redact::Redaction;
+pub use spans::{DuplicateFields, SpanSelection};
+use std::{any::Any, borrow::Cow, cell::Cell, fmt, sync::Arc};
+pub use theme::{HashColors, ParseThemeError, Theme, ThemeElement};
 use tracing::{
     field::{Field, Visit},
-    Subscriber,
+    span::Record,
+    Level, Subscriber,
 };
 #[cfg(feature = "tracing-log")]
 use tracing_log::NormalizeEvent;
 use tracing_subscriber::{
-    field::{MakeVisitor, VisitFmt, VisitOutput},
+    field::{RecordFields, VisitFmt, VisitOutput},
     fmt::{
-        format::Writer, time::FormatTime, FmtContext, FormatEvent, FormatFields, FormattedFields,
+        format::{FmtSpan, Writer},
+        time::FormatTime,
+        FmtContext, FormatEvent, FormatFields, FormattedFields,
     },
-    registry::LookupSpan,
+    registry::{Extensions, LookupSpan, Scope, SpanRef},
 };
+pub use value::{BytesFormat, ValueFormat};
 
-use crate::format::{FormatProcessData, FormatSpanFields};
+#[cfg(feature = "tokio")]
+use crate::format::TokioTask;
+use crate::{
+    format::{
+        Column, Continued, Counting, FormatLocation, FormatProcessData, FormatSpanFields, Painted,
+        Restyle, SpanLifecycle, Truncate, Truncated,
+    },
+    hyperlink::Hyperlink,
+    layout::Segment,
+};
 
 /// A [glog]-inspired span and event formatter.
 ///
 /// [glog]: https://github.com/google/glog
-pub struct Glog<T = UtcTime> {
+pub struct Glog<T = UtcTime, P = DefaultPrefix> {
     timer: T,
-    level_chars: &'static FormatLevelChars,
+    prefix: P,
+    level_chars: Cow<'static, FormatLevelChars>,
     with_span_context: bool,
     with_thread_names: bool,
+    thread_name_column: Column,
     with_target: bool,
+    target_column: Column,
+    file_path: FilePath,
+    location_column: Column,
     with_span_names: bool,
+    span_events: FmtSpan,
+    #[cfg(feature = "tokio")]
+    with_tokio_tasks: bool,
+    theme: Theme,
+    with_line_coloring: bool,
+    layout: Option<Layout>,
+    hyperlinks: Option<Hyperlinks>,
+    multiline: Multiline,
+    max_span_context_len: Option<usize>,
+    max_line_len: Option<usize>,
+    span_selection: Option<SpanSelection>,
+    duplicate_fields: DuplicateFields,
+    promoted_fields: Vec<String>,
 }
 
-impl<T> Glog<T> {
+impl<T, P> Glog<T, P> {
     /// Use the given [timer] for span and event time stamps.
     ///
     /// `tracing-glog` provides two timers: [`LocalTime`] and [`UtcTime`].
     /// [`UtcTime`] is the default timer.
     ///
     /// [timer]: tracing_subscriber::fmt::time::FormatTime
-    pub fn with_timer<T2>(self, timer: T2) -> Glog<T2>
+    pub fn with_timer<T2>(self, timer: T2) -> Glog<T2, P>
     where
         T2: FormatTime,
     {
         Glog {
             timer,
+            prefix: self.prefix,
+            level_chars: self.level_chars,
+            with_thread_names: self.with_thread_names,
+            thread_name_column: self.thread_name_column,
+            with_target: self.with_target,
+            target_column: self.target_column,
+            file_path: self.file_path,
+            location_column: self.location_column,
+            with_span_context: self.with_span_context,
+            with_span_names: self.with_span_names,
+            span_events: self.span_events,
+            #[cfg(feature = "tokio")]
+            with_tokio_tasks: self.with_tokio_tasks,
+            theme: self.theme,
+            with_line_coloring: self.with_line_coloring,
+            layout: self.layout,
+            hyperlinks: self.hyperlinks,
+            multiline: self.multiline,
+            max_span_context_len: self.max_span_context_len,
+            max_line_len: self.max_line_len,
+            span_selection: self.span_selection,
+            duplicate_fields: self.duplicate_fields,
+            promoted_fields: self.promoted_fields,
+        }
+    }
+
+    /// Use the given [`FormatPrefix`] to format the prefix of each line, replacing the
+    /// level, timestamp, PID, thread name, target and source location. The span
+    /// context and the fields are still printed by `Glog`.
+    ///
+    /// Note that the methods that configure the default prefix, such as
+    /// [`Glog::with_thread_names`], only apply to the prefix written by
+    /// [`Prefix::format_default`].
+    pub fn with_prefix_formatter<P2>(self, prefix: P2) -> Glog<T, P2> {
+        Glog {
+            timer: self.timer,
+            prefix,
             level_chars: self.level_chars,
             with_thread_names: self.with_thread_names,
+            thread_name_column: self.thread_name_column,
             with_target: self.with_target,
+            target_column: self.target_column,
+            file_path: self.file_path,
+            location_column: self.location_column,
             with_span_context: self.with_span_context,
             with_span_names: self.with_span_names,
+            span_events: self.span_events,
+            #[cfg(feature = "tokio")]
+            with_tokio_tasks: self.with_tokio_tasks,
+            theme: self.theme,
+            with_line_coloring: self.with_line_coloring,
+            layout: self.layout,
+            hyperlinks: self.hyperlinks,
+            multiline: self.multiline,
+            max_span_context_len: self.max_span_context_len,
+            max_line_len: self.max_line_len,
+            span_selection: self.span_selection,
+            duplicate_fields: self.duplicate_fields,
+            promoted_fields: self.promoted_fields,
         }
     }
 
-    pub fn with_thread_names(self, with_thread_names: bool) -> Glog<T> {
+    pub fn with_thread_names(self, with_thread_names: bool) -> Glog<T, P> {
         Glog {
             with_thread_names,
             ..self
         }
     }
 
-    pub fn with_target(self, with_target: bool) -> Glog<T> {
+    pub fn with_target(self, with_target: bool) -> Glog<T, P> {
         Glog {
             with_target,
             ..self
         }
     }
 
+    /// Sets the width of the thread name column. Defaults to [`ColumnWidth::Natural`].
+    ///
+    /// Along with [`Glog::with_target_width`] and [`Glog::with_location_width`], this
+    /// lines up the columns of the prefix so that lines are easier to scan.
+    ///
+    /// # Example Output
+    /// With thread names set to [`ColumnWidth::Adaptive`]:
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 main examples/tokio.rs:8] [parent_task{subtasks: 10}] spawning subtasks...
+    /// I0731 16:23:45.674524 990039 tokio-runtime-worker examples/tokio.rs:28] [subtask{number: 1}] polling subtask, number: 1
+    /// I0731 16:23:45.674560 990039 main                 examples/tokio.rs:21] [parent_task{subtasks: 10}] task completed, task: 1
+    /// </pre>
+    pub fn with_thread_name_width(self, width: ColumnWidth) -> Glog<T, P> {
+        Glog {
+            thread_name_column: Column::new(width),
+            ..self
+        }
+    }
+
+    /// Sets the width of the target column, including its brackets. Defaults to
+    /// [`ColumnWidth::Natural`].
+    pub fn with_target_width(self, width: ColumnWidth) -> Glog<T, P> {
+        Glog {
+            target_column: Column::new(width),
+            ..self
+        }
+    }
+
+    /// Sets the width of the source location (`file:line`) column. Defaults to
+    /// [`ColumnWidth::Natural`].
+    ///
+    /// Locations are aligned to the right so that the `]` closing the prefix lines up.
+    pub fn with_location_width(self, width: ColumnWidth) -> Glog<T, P> {
+        Glog {
+            location_column: Column::new(width),
+            ..self
+        }
+    }
+
+    /// Sets whether or not the current [tokio task] is included. Defaults to false.
+    ///
+    /// Thread names and IDs change as a task migrates between a runtime's workers, so
+    /// they are of limited use for following asynchronous code. When enabled, the
+    /// task's ID is printed after the thread name, followed by the task's name if it has
+    /// one. Nothing is printed for events recorded outside of a task, such as from
+    /// `block_on` or from outside of a tokio runtime.
+    ///
+    /// Task names are only available when tokio's own instrumentation is enabled (that
+    /// is, when tokio is compiled with `--cfg tokio_unstable` and its `tracing` feature)
+    /// and its `runtime.spawn` spans are enabled for this layer.
+    ///
+    /// # Example Output
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 tokio-runtime-worker task:12(subtask) examples/tokio.rs:38] [subtask{number: 10}] polling subtask, number: 10
+    /// </pre>
+    ///
+    /// [tokio task]: https://docs.rs/tokio/latest/tokio/task/index.html
+    #[cfg(feature = "tokio")]
+    pub fn with_tokio_tasks(self, with_tokio_tasks: bool) -> Glog<T, P> {
+        Glog {
+            with_tokio_tasks,
+            ..self
+        }
+    }
+
+    /// Sets how the source file of each event is printed. Defaults to [`FilePath::Full`].
+    ///
+    /// Events without a source file, such as some events converted from `log` records,
+    /// print their module path instead.
+    ///
+    /// # Example Output
+    /// With [`FilePath::ShortenRegistry`]:
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 tokio@1.21.0/src/runtime/mod.rs:38] shutting down runtime
+    /// </pre>
+    ///
+    /// With [`FilePath::Basename`]:
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 mod.rs:38] shutting down runtime
+    /// </pre>
+    pub fn with_file_path(self, file_path: FilePath) -> Glog<T, P> {
+        Glog { file_path, ..self }
+    }
+
     /// Sets whether or not the span name is included. Defaults to true.
     ///
     /// If span names are not included, then the fields from all spans are
@@ -208,7 +409,7 @@ impl<T> Glog<T> {
     /// <pre>
     /// I0731 16:23:45.674465 990039 examples/tokio.rs:38] [subtasks: 10, reason: "testing", number: 10] polling subtask, number: 10
     /// </pre>
-    pub fn with_span_names(self, with_span_names: bool) -> Glog<T> {
+    pub fn with_span_names(self, with_span_names: bool) -> Glog<T, P> {
         Glog {
             with_span_names,
             ..self
@@ -228,64 +429,417 @@ impl<T> Glog<T> {
     ///
     /// **Notice:** This is a relatively coarse toggle. In most circumstances, usage of
     /// `tracing-subscriber`'s [`filter_fn`] is preferred to disable spans on a more
-    /// fine-grained basis.
+    /// fine-grained basis, and [`Glog::with_span_selection`] to only hide them from the
+    /// span context.
     ///
     /// [`fmt::Layer`]: tracing_subscriber::fmt::Layer
     /// [`fmt::Subscriber`]: tracing_subscriber::fmt::Subscriber
     /// [per-layer filtering]: https://docs.rs/tracing-subscriber/latest/tracing_subscriber/layer/index.html#per-layer-filtering
     /// [`filter_fn`]: fn@tracing_subscriber::filter::filter_fn
     /// [`tracing`]: mod@tracing
-    pub fn with_span_context(self, with_span_context: bool) -> Glog<T> {
+    pub fn with_span_context(self, with_span_context: bool) -> Glog<T, P> {
         Glog {
             with_span_context,
             ..self
         }
     }
 
-    /// Sets the characters to use to indicate the level for each event.
-    /// Defaults to the initial character of the level.
-    pub fn with_format_level_chars(self, level_chars: &'static FormatLevelChars) -> Glog<T> {
+    /// Sets which spans are printed in the span context, see [`SpanSelection`]. By
+    /// default, all of them are.
+    ///
+    /// # Example Output
+    /// With a maximum depth of 2:
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 examples/tokio.rs:38] [parent_task{subtasks: 10}, ..., subtask{number: 10}] polling subtask, number: 10
+    /// </pre>
+    pub fn with_span_selection(self, span_selection: SpanSelection) -> Glog<T, P> {
         Glog {
-            level_chars,
+            span_selection: Some(span_selection),
             ..self
         }
     }
-}
 
-impl Default for Glog<UtcTime> {
-    fn default() -> Self {
+    /// Sets which value is printed for fields that several spans in the span context
+    /// share. Defaults to [`DuplicateFields::Keep`].
+    ///
+    /// Fields are matched by name, whether or not span names are printed. Spans left
+    /// without fields are printed without braces, or not at all without span names.
+    ///
+    /// # Example Output
+    /// With [`DuplicateFields::Innermost`] and `with_span_names` set to false:
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 examples/tokio.rs:38] [request_id: 7, subtasks: 10, number: 10] polling subtask, number: 10
+    /// </pre>
+    pub fn with_duplicate_span_fields(self, duplicate_fields: DuplicateFields) -> Glog<T, P> {
         Glog {
-            timer: UtcTime::default(),
-            level_chars: &format::DEFAULT_FORMAT_LEVEL_CHARS,
-            with_thread_names: false,
-            with_target: false,
-            with_span_context: true,
-            with_span_names: true,
+            duplicate_fields,
+            ..self
+        }
+    }
+
+    /// Promotes the given span fields into the prefix, where they are printed as
+    /// `key=value` right after it, in the given order. By default, no fields are
+    /// promoted.
+    ///
+    /// Each field's value is taken from the innermost span that has the field, and
+    /// fields that no span has are printed as `key=-`, so that every line has the same
+    /// columns. Values are quoted as in logfmt when needed. Promoted fields are left
+    /// out of the span context, unless a [`Layout`] without a `{promoted}`
+    /// placeholder is used.
+    ///
+    /// # Example Output
+    /// With `request_id` and `tenant` promoted:
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 examples/server.rs:38] request_id=7 tenant=- [handle{path: "/"}] handling request
+    /// </pre>
+    pub fn with_promoted_fields<I>(self, fields: I) -> Glog<T, P>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Glog {
+            promoted_fields: fields.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Sets which span lifecycle events are formatted. Defaults to [`FmtSpan::FULL`].
+    ///
+    /// Lifecycle events are emitted by [`fmt::Layer`] and [`fmt::Subscriber`] once
+    /// enabled with their `with_span_events` method, and are formatted as glog lines
+    /// attributed to the span's definition. The span's parents are printed as the
+    /// span context, followed by the kind of event and the span's own name and fields.
+    /// Close events also include the time the span was busy and idle:
+    ///
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 examples/yak-shave.rs:68] [shaving_yaks{yaks: 3}] close shave{yak: 2}, time.busy: 38.2µs, time.idle: 11.0µs
+    /// </pre>
+    ///
+    /// Lifecycle events whose kind is not included in `span_events` are not printed,
+    /// so, for example, the layer can emit [`FmtSpan::FULL`] while this formatter only
+    /// prints `FmtSpan::NEW | FmtSpan::CLOSE`.
+    ///
+    /// [`fmt::Layer`]: tracing_subscriber::fmt::Layer
+    /// [`fmt::Subscriber`]: tracing_subscriber::fmt::Subscriber
+    pub fn with_span_events(self, span_events: FmtSpan) -> Glog<T, P> {
+        Glog {
+            span_events,
+            ..self
+        }
+    }
+
+    /// Sets the [`Theme`] used to style the prefix and the span context when ANSI escape
+    /// codes are enabled. Defaults to [`Theme::default`].
+    ///
+    /// Field names and messages are styled by [`GlogFields`], so the same theme should
+    /// usually be passed to [`GlogFields::with_theme`] as well.
+    pub fn with_theme(self, theme: Theme) -> Glog<T, P> {
+        Glog { theme, ..self }
+    }
+
+    /// Sets whether or not `WARN` and `ERROR` lines are colored entirely in the
+    /// severity's color, as glog does with `--colorlogtostderr`. Defaults to false.
+    ///
+    /// The styles of the individual elements of the line, such as the bold span
+    /// names, are layered on top of the severity's color rather than replacing it.
+    /// Lines at other levels are styled as usual.
+    ///
+    /// Fields are styled within the line when they are formatted by [`GlogFields`].
+    /// Other field formatters print them without styling. Values selected by the
+    /// theme's [`HashColors`] aren't colored by their hash on these lines, so that the
+    /// whole line keeps the severity's color.
+    pub fn with_line_coloring(self, with_line_coloring: bool) -> Glog<T, P> {
+        Glog {
+            with_line_coloring,
+            ..self
+        }
+    }
+
+    /// Makes the source location of each event a hyperlink, see [`Hyperlinks`]. By
+    /// default, there are no hyperlinks.
+    pub fn with_hyperlinks(self, hyperlinks: Hyperlinks) -> Glog<T, P> {
+        Glog {
+            hyperlinks: Some(hyperlinks),
+            ..self
+        }
+    }
+
+    /// Sets the [`Layout`] of each line, replacing the default prefix, span context
+    /// and fields. By default, there is no layout.
+    ///
+    /// The rest of `Glog`'s configuration, such as the timer and the column widths,
+    /// still applies to the elements of the layout. A layout takes precedence over a
+    /// custom [`FormatPrefix`].
+    ///
+    /// # Example Output
+    /// With `{level} {time} {location}] {message} {fields} {spans}`:
+    /// <pre>
+    /// W 0731 16:23:45.674465 examples/yak-shave.rs:56] could not locate yak [shaving_yaks{yaks: 3}, shave{yak: 3}]
+    /// </pre>
+    pub fn with_layout(self, layout: Layout) -> Glog<T, P> {
+        Glog {
+            layout: Some(layout),
+            ..self
+        }
+    }
+
+    /// Sets how messages and fields that span several lines are printed. Defaults to
+    /// [`Multiline::Keep`].
+    ///
+    /// With a [`Layout`], the prefix repeated by [`Multiline::RepeatPrefix`] is
+    /// everything before the first of the `{spans}`, `{message}` and `{fields}`
+    /// placeholders. Fields formatted by a formatter other than [`GlogFields`] are
+    /// printed without styling unless lines are kept as they are.
+    ///
+    /// # Example Output
+    /// With [`Multiline::RepeatPrefix`]:
+    /// <pre>
+    /// E0731 16:23:45.674465 990039 examples/yak-shave.rs:85] [shaving_yaks{yaks: 3}] failed to shave yak, error: Error {
+    /// E0731 16:23:45.674465 990039 examples/yak-shave.rs:85] [...]     kind: OutOfCash,
+    /// E0731 16:23:45.674465 990039 examples/yak-shave.rs:85] [...] }
+    /// </pre>
+    pub fn with_multiline(self, multiline: Multiline) -> Glog<T, P> {
+        Glog { multiline, ..self }
+    }
+
+    /// Sets the maximum length of the span context, including its brackets, in bytes.
+    /// By default, there is no maximum.
+    ///
+    /// A longer span context is cut and followed by a `...[truncated N bytes]` marker
+    /// and its closing bracket. ANSI escape codes don't count towards the length.
+    pub fn with_max_span_context_len(self, max_span_context_len: usize) -> Glog<T, P> {
+        Glog {
+            max_span_context_len: Some(max_span_context_len),
+            ..self
+        }
+    }
+
+    /// Sets the maximum length of each line, excluding the trailing newline, in bytes.
+    /// By default, there is no maximum, while glog truncates messages to 30000 bytes.
+    ///
+    /// Longer lines are cut and followed by a `...[truncated N bytes]` marker. ANSI
+    /// escape codes don't count towards the length. When an event spans several
+    /// lines, such as with [`Multiline::RepeatPrefix`], the maximum applies to each
+    /// line separately, so that every line keeps its prefix.
+    pub fn with_max_line_len(self, max_line_len: usize) -> Glog<T, P> {
+        Glog {
+            max_line_len: Some(max_line_len),
+            ..self
+        }
+    }
+
+    /// Sets the labels used to indicate the level of each event. Accepts both owned
+    /// and `&'static` [`FormatLevelChars`]. Defaults to [`FormatLevelChars::letters`].
+    ///
+    /// # Example Output
+    /// With [`FormatLevelChars::padded_names`]:
+    /// <pre>
+    /// INFO    0731 16:23:45.674465 990039 examples/yak-shave.rs:34] preparing to shave yaks, number_of_yaks: 3
+    /// WARNING 0731 16:23:45.674528 990039 examples/yak-shave.rs:56] [shaving_yaks{yaks: 3}, shave{yak: 3}] could not locate yak
+    /// </pre>
+    pub fn with_format_level_chars(
+        self,
+        level_chars: impl Into<Cow<'static, FormatLevelChars>>,
+    ) -> Glog<T, P> {
+        Glog {
+            level_chars: level_chars.into(),
+            ..self
         }
     }
 }
 
-impl<S, N, T> FormatEvent<S, N> for Glog<T>
-where
-    S: Subscriber + for<'a> LookupSpan<'a>,
-    N: for<'a> FormatFields<'a> + 'static,
-    T: FormatTime,
-{
-    fn format_event(
+impl<T, P> Glog<T, P> {
+    /// Prints the span context into brackets of `[]`, which glog parsers ignore.
+    ///
+    /// Returns whether or not anything was printed.
+    fn format_span_context<S, N>(
+        &self,
+        writer: &mut Writer<'_>,
+        fmt_fields: &N,
+        theme: &Theme,
+        scope: Option<Scope<'_, S>>,
+    ) -> Result<bool, fmt::Error>
+    where
+        S: for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        let max = match self.max_span_context_len {
+            Some(max) => max,
+            None => return self.write_span_context(writer, fmt_fields, theme, scope),
+        };
+        let mut truncate = Truncate::new(writer, max);
+        let mut writer = Writer::new(&mut truncate);
+        let wrote = self.write_span_context(&mut writer, fmt_fields, theme, scope)?;
+        truncate.finish()?;
+        Ok(wrote)
+    }
+
+    fn write_span_context<S, N>(
+        &self,
+        writer: &mut Writer<'_>,
+        fmt_fields: &N,
+        theme: &Theme,
+        scope: Option<Scope<'_, S>>,
+    ) -> Result<bool, fmt::Error>
+    where
+        S: for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        let scope = match scope {
+            Some(scope) => scope,
+            None => return Ok(false),
+        };
+        let mut wrote_open_bracket = false;
+
+        // Write the name and fields of a span, or `...` in place of elided spans.
+        let mut write_entry = |span: Option<(&'static str, Option<&str>)>| -> fmt::Result {
+            let (name, fields) = match span {
+                Some(span) => span,
+                None => {
+                    let separator = if wrote_open_bracket { ", " } else { "[" };
+                    wrote_open_bracket = true;
+                    return write!(writer, "{separator}...");
+                }
+            };
+
+            if self.with_span_names || fields.is_some() {
+                if wrote_open_bracket {
+                    write!(writer, ", ")?;
+                } else {
+                    // Write the opening bracket once we know we need one
+                    write!(writer, "[")?;
+                    wrote_open_bracket = true;
+                }
+                let fields =
+                    FormatSpanFields::format_fields(name, fields, theme, self.with_span_names);
+                write!(writer, "{fields}")?;
+            }
+            Ok(())
+        };
+
+        let promoted = self.printed_promoted_fields();
+        let all_fields = self.duplicate_fields == DuplicateFields::Keep && promoted.is_empty();
+        if self.span_selection.is_none() && all_fields {
+            for span in scope.from_root() {
+                let ext = span.extensions();
+                write_entry(Some((span.name(), span_fields::<N>(&ext))))?;
+            }
+        } else {
+            let spans: Vec<_> = match &self.span_selection {
+                Some(selection) => scope
+                    .from_root()
+                    .filter(|span| selection.selects(span.metadata()))
+                    .collect(),
+                None => scope.from_root().collect(),
+            };
+            let visible = match &self.span_selection {
+                Some(selection) => selection.visible(spans.len()),
+                None => (0..spans.len()).map(Some).collect(),
+            };
+
+            let rendered: Vec<Option<String>> = visible
+                .iter()
+                .map(|idx| {
+                    let ext = spans[(*idx)?].extensions();
+                    span_fields::<N>(&ext).map(str::to_string)
+                })
+                .collect();
+            let separator = field_separator(fmt_fields);
+            let mut fields: Vec<Vec<&str>> = rendered
+                .iter()
+                .map(|fields| split_fields(fields.as_deref().unwrap_or_default(), separator))
+                .collect();
+            self.duplicate_fields.dedup(&mut fields, field_key);
+            for fields in &mut fields {
+                fields.retain(|field| {
+                    field_key(field).map_or(true, |key| !promoted.iter().any(|p| p == key))
+                });
+            }
+
+            for (idx, fields) in visible.into_iter().zip(fields) {
+                let fields = Some(fields.join(separator)).filter(|fields| !fields.is_empty());
+                write_entry(idx.map(|idx| (spans[idx].name(), fields.as_deref())))?;
+            }
+        }
+        if wrote_open_bracket {
+            write!(writer, "]")?;
+        }
+        Ok(wrote_open_bracket)
+    }
+
+    /// Returns the promoted fields that are printed, which are left out of the span
+    /// context. A [`Layout`] only prints them if it has a `{promoted}` placeholder.
+    fn printed_promoted_fields(&self) -> &[String] {
+        match &self.layout {
+            Some(layout) if !layout.segments.contains(&Segment::Promoted) => &[],
+            _ => &self.promoted_fields,
+        }
+    }
+
+    /// Prints a span lifecycle event (everything after the prefix).
+    ///
+    /// A lifecycle event's parent is the span itself, so its parents are printed as the
+    /// span context, and the span is printed alongside the kind of event.
+    fn format_span_lifecycle<S, N>(
         &self,
         ctx: &FmtContext<'_, S, N>,
-        mut writer: Writer<'_>,
+        writer: &mut Writer<'_>,
+        theme: &Theme,
+        style: LineStyle,
         event: &tracing::Event<'_>,
-    ) -> fmt::Result {
-        let level = *event.metadata().level();
-
-        // Convert log level to a single character representation.)
-        let level = FmtLevel::format_level(level, self.level_chars, writer.has_ansi_escapes());
-        write!(writer, "{level}")?;
+        lifecycle: &SpanLifecycle,
+    ) -> fmt::Result
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        let span = match ctx.parent_span() {
+            Some(span) => span,
+            None => return write!(writer, "{}", lifecycle.label()),
+        };
+        if self.with_span_context {
+            let scope = span.parent().map(|parent| parent.scope());
+            if self.format_span_context(writer, ctx.field_format(), theme, scope)? {
+                write!(writer, " ")?;
+            }
+        }
 
-        // write the timestamp:
-        self.timer.format_time(&mut writer)?;
+        // the message is written as is, so that it isn't escaped, redacted or truncated
+        // like a field; only the timings are.
+        let fmt_fields = ctx.field_format();
+        write!(
+            writer,
+            "{}",
+            lifecycle_message::<S, N>(&span, lifecycle, theme)
+        )?;
+        if !lifecycle.has_timings() {
+            return Ok(());
+        }
+        write!(writer, "{}", field_separator(fmt_fields))?;
+        lifecycle.with_event(event, |event| {
+            format_event_fields(fmt_fields, writer.by_ref(), event, style, Fields::All)
+        })
+    }
 
+    /// Prints everything on an event's line but the trailing newline.
+    ///
+    /// `writer` may not have ANSI escape codes enabled even when they are in use (see
+    /// [`format_event_fields`]), so only `theme` and `style` decide on styling.
+    fn format_line<S, N>(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        theme: &Theme,
+        style: LineStyle,
+        event: &tracing::Event<'_>,
+        lifecycle: Option<SpanLifecycle>,
+    ) -> fmt::Result
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+        T: FormatTime,
+        P: FormatPrefix<S>,
+    {
         // get some process information
         let pid = get_pid();
         let thread = std::thread::current();
@@ -302,86 +856,635 @@ where
             pid,
             thread_name,
             with_thread_names: self.with_thread_names,
+            thread_name_column: &self.thread_name_column,
+            #[cfg(feature = "tokio")]
+            task: tokio::task::try_id()
+                .filter(|_| self.with_tokio_tasks)
+                .map(|id| TokioTask {
+                    id,
+                    name: ctx
+                        .lookup_current()
+                        .and_then(|span| tokio_task_name(span.scope(), ctx.field_format())),
+                }),
             metadata,
+            file_path: &self.file_path,
+            location_column: &self.location_column,
             with_target: self.with_target,
-            #[cfg(feature = "ansi")]
-            ansi: writer.has_ansi_escapes(),
+            target_column: &self.target_column,
+            hyperlinks: self.hyperlinks.as_ref().filter(|_| style.ansi),
+            theme,
+        };
+        let prefix = Prefix {
+            // Convert log level to a single character representation.
+            level: FmtLevel::format_level(*metadata.level(), &self.level_chars, theme),
+            timer: &self.timer,
+            data,
+            span: ctx.event_scope().and_then(|mut scope| scope.next()),
         };
-        write!(writer, "{data}] ")?;
-
-        if self.with_span_context {
-            // now, we're printing the span context into brackets of `[]`, which glog parsers ignore.
-            let leaf = ctx.lookup_current();
-
-            if let Some(leaf) = leaf {
-                let mut wrote_open_bracket = false;
-
-                // Write spans and fields of each span
-                let mut iter = leaf.scope().from_root();
-                let mut span = iter.next().expect(
-                    "Unable to get the next item in the iterator; this should not be possible.",
-                );
-                loop {
-                    let ext = span.extensions();
-                    let fields = &ext
-                        .get::<FormattedFields<N>>()
-                        .expect("will never be `None`");
-
-                    let fields = if !fields.is_empty() {
-                        Some(fields.as_str())
-                    } else {
-                        None
-                    };
 
-                    if self.with_span_names || fields.is_some() {
-                        if !wrote_open_bracket {
-                            // Write the opening bracket once we know we need one
-                            write!(writer, "[")?;
-                            wrote_open_bracket = true;
-                        }
-                        let fields = FormatSpanFields::format_fields(
-                            span.name(),
-                            fields,
-                            writer.has_ansi_escapes(),
-                            self.with_span_names,
-                        );
-                        write!(writer, "{fields}")?;
-                    }
+        if self.multiline == Multiline::Keep {
+            if let Some(layout) = &self.layout {
+                return self
+                    .format_layout(ctx, writer, style, event, lifecycle, &prefix, layout, None);
+            }
+            self.format_prefix(&mut writer, ctx.field_format(), &prefix)?;
+            return self.format_body(ctx, writer, theme, style, event, lifecycle);
+        }
 
-                    drop(ext);
-                    match iter.next() {
-                        // if there's more, add a space.
-                        Some(next) => {
-                            if wrote_open_bracket {
-                                write!(writer, ", ")?;
-                            }
-                            span = next;
-                        }
-                        // if there's nothing there, close.
-                        None => break,
-                    }
-                }
-                if wrote_open_bracket {
-                    write!(writer, "] ")?;
-                }
+        // Render the prefix and the rest of the line separately, so that the prefix can
+        // be repeated on continuation lines.
+        let mut line = String::new();
+        let body_start = Cell::new(None);
+        if let Some(layout) = &self.layout {
+            let layout_writer = Writer::new(&mut line);
+            let body = Some(&body_start);
+            self.format_layout(
+                ctx,
+                layout_writer,
+                style,
+                event,
+                lifecycle,
+                &prefix,
+                layout,
+                body,
+            )?;
+        } else {
+            self.format_prefix(&mut Writer::new(&mut line), ctx.field_format(), &prefix)?;
+            body_start.set(Some(line.len()));
+            self.format_body(ctx, Writer::new(&mut line), theme, style, event, lifecycle)?;
+        }
+        // the body can start after the end of the line if it's empty and the space
+        // before it was dropped.
+        let body_start = body_start.get().unwrap_or(line.len()).min(line.len());
+        let (head, body) = line.split_at(body_start);
+        let multiline = self.multiline;
+        let ansi = style.ansi;
+        write!(
+            writer,
+            "{}",
+            Continued {
+                head,
+                body,
+                multiline,
+                ansi
             }
+        )
+    }
+
+    /// Prints the prefix with [`FormatPrefix`], followed by the promoted fields.
+    fn format_prefix<S, N>(
+        &self,
+        writer: &mut Writer<'_>,
+        fmt_fields: &N,
+        prefix: &Prefix<'_, S>,
+    ) -> fmt::Result
+    where
+        S: for<'a> LookupSpan<'a>,
+        N: 'static,
+        P: FormatPrefix<S>,
+    {
+        self.prefix.format_prefix(writer, prefix)?;
+        if self.promoted_fields.is_empty() {
+            return Ok(());
         }
-        ctx.field_format().format_fields(writer.by_ref(), event)?;
-        writeln!(writer)
+        self.format_promoted_fields(writer, fmt_fields, prefix)?;
+        write!(writer, " ")
     }
-}
+
+    /// Prints the promoted fields, separated by spaces.
+    fn format_promoted_fields<S, N>(
+        &self,
+        writer: &mut Writer<'_>,
+        fmt_fields: &N,
+        prefix: &Prefix<'_, S>,
+    ) -> fmt::Result
+    where
+        S: for<'a> LookupSpan<'a>,
+        N: 'static,
+    {
+        let key_style = prefix.theme().field_key;
+        let separator = field_separator(fmt_fields);
+        for (idx, name) in self.promoted_fields.iter().enumerate() {
+            let value = prefix.span.as_ref().and_then(|span| {
+                span.scope().find_map(|span| {
+                    let ext = span.extensions();
+                    field_value(span_fields::<N>(&ext)?, name, separator).map(Cow::into_owned)
+                })
+            });
+            let separator = if idx == 0 { "" } else { " " };
+            let name = Painted {
+                value: name,
+                style: key_style,
+            };
+            match value {
+                Some(value) => write!(
+                    writer,
+                    "{separator}{name}={}",
+                    LogfmtValue(&value, Escape::All)
+                )?,
+                None => write!(writer, "{separator}{name}=-")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints everything on an event's line after the prefix.
+    fn format_body<S, N>(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        theme: &Theme,
+        style: LineStyle,
+        event: &tracing::Event<'_>,
+        lifecycle: Option<SpanLifecycle>,
+    ) -> fmt::Result
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        match lifecycle {
+            Some(lifecycle) => {
+                self.format_span_lifecycle(ctx, &mut writer, theme, style, event, &lifecycle)
+            }
+            None => {
+                if self.with_span_context {
+                    // `event_scope` honors an explicit `parent:` on the event (including
+                    // `parent: None`) and only falls back to the current span for contextual
+                    // events.
+                    let scope = ctx.event_scope();
+                    if self.format_span_context(&mut writer, ctx.field_format(), theme, scope)? {
+                        write!(writer, " ")?;
+                    }
+                }
+                format_event_fields(ctx.field_format(), writer, event, style, Fields::All)
+            }
+        }
+    }
+
+    /// Prints a line according to a [`Layout`] (everything but the trailing newline).
+    ///
+    /// If `body_start` is set, it is set to the number of bytes written before the
+    /// first placeholder of the body of the line: the span context, the message or the
+    /// fields.
+    #[allow(clippy::too_many_arguments)]
+    fn format_layout<S, N>(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        writer: Writer<'_>,
+        style: LineStyle,
+        event: &tracing::Event<'_>,
+        lifecycle: Option<SpanLifecycle>,
+        prefix: &Prefix<'_, S>,
+        layout: &Layout,
+        body_start: Option<&Cell<Option<usize>>>,
+    ) -> fmt::Result
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        let fmt_fields = ctx.field_format();
+        let lifecycle = match lifecycle {
+            Some(lifecycle) => lifecycle,
+            None => {
+                let scope = ctx.event_scope();
+                return self.render_layout(
+                    fmt_fields, writer, style, event, None, scope, prefix, layout, body_start,
+                );
+            }
+        };
+        let span = match ctx.parent_span() {
+            Some(span) => span,
+            None => {
+                return self.render_layout(
+                    fmt_fields, writer, style, event, None, None, prefix, layout, body_start,
+                )
+            }
+        };
+
+        let message = lifecycle_message::<S, N>(&span, &lifecycle, prefix.theme());
+        let scope = span.parent().map(|parent| parent.scope());
+        lifecycle.with_event(event, |event| {
+            let message = Some(message.as_str());
+            self.render_layout(
+                fmt_fields, writer, style, event, message, scope, prefix, layout, body_start,
+            )
+        })
+    }
+
+    /// Renders `layout` for `event`. If `message` is set, it is printed as is in place
+    /// of the event's message, as for span lifecycle events.
+    #[allow(clippy::too_many_arguments)]
+    fn render_layout<S, N>(
+        &self,
+        fmt_fields: &N,
+        mut writer: Writer<'_>,
+        style: LineStyle,
+        event: &tracing::Event<'_>,
+        message: Option<&str>,
+        scope: Option<Scope<'_, S>>,
+        prefix: &Prefix<'_, S>,
+        layout: &Layout,
+        body_start: Option<&Cell<Option<usize>>>,
+    ) -> fmt::Result
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        let data = &prefix.data;
+        let theme = data.theme;
+        let metadata = data.metadata;
+        // `Writer` doesn't tell how much was written, so count it to find placeholders
+        // that print nothing. The trailing space of a literal is held back until the
+        // next placeholder prints something, so that the line doesn't end in a space.
+        let written = Cell::new(0);
+        let space = Cell::new(false);
+        let mut counting = Counting::new(&mut writer, &written, &space);
+        let mut writer = Writer::new(&mut counting);
+        let mut scope = scope.filter(|_| self.with_span_context);
+        let mut skip_space = false;
+
+        for segment in &layout.segments {
+            let before = written.get();
+            if let Some(body_start) = body_start {
+                let is_body =
+                    matches!(segment, Segment::Spans | Segment::Message | Segment::Fields);
+                if is_body && body_start.get().is_none() {
+                    // the body starts after the space that is held back, if any.
+                    body_start.set(Some(before + usize::from(space.get())));
+                }
+            }
+            match segment {
+                Segment::Literal(text) => {
+                    let text = match skip_space {
+                        true => text.strip_prefix(' ').unwrap_or(text),
+                        false => text,
+                    };
+                    match text.strip_suffix(' ') {
+                        Some(text) => {
+                            writer.write_str(text)?;
+                            space.set(true);
+                        }
+                        None => writer.write_str(text)?,
+                    }
+                    skip_space = false;
+                    continue;
+                }
+                Segment::Level => write!(writer, "{}", prefix.level)?,
+                Segment::Time => {
+                    write!(writer, "{}", theme.timestamp.prefix())?;
+                    prefix.write_timestamp(&mut writer)?;
+                    write!(writer, "{}", theme.timestamp.suffix())?;
+                }
+                Segment::Pid => write_styled(&mut writer, &data.pid, theme.pid)?,
+                Segment::Tid => write_styled(&mut writer, &get_tid(), theme.pid)?,
+                Segment::Thread => {
+                    if let Some(name) = data.thread_name {
+                        let column = data.thread_name_column;
+                        let style = theme.thread_name_style(name);
+                        column.write(&mut writer, &name, style, false, false, None)?;
+                    }
+                }
+                #[cfg(feature = "tokio")]
+                Segment::Task => {
+                    if let Some(task) = &data.task {
+                        write_styled(&mut writer, task, theme.task_style(&task.id))?;
+                    }
+                }
+                Segment::Target => {
+                    let column = data.target_column;
+                    let target = metadata.target();
+                    column.write(&mut writer, &target, theme.target, false, true, None)?;
+                }
+                Segment::File => {
+                    if let Some(file) = metadata.file() {
+                        let file = data.file_path.display(file);
+                        let url = data.location_url();
+                        let text = Painted {
+                            value: &file,
+                            style: theme.location,
+                        };
+                        write!(
+                            writer,
+                            "{}",
+                            Hyperlink {
+                                url: url.as_deref(),
+                                text
+                            }
+                        )?;
+                    }
+                }
+                Segment::Line => {
+                    if let Some(line) = metadata.line() {
+                        write_styled(&mut writer, &line, theme.location)?;
+                    }
+                }
+                Segment::Location => {
+                    let location = FormatLocation {
+                        metadata,
+                        file_path: data.file_path,
+                    };
+                    let url = data.location_url();
+                    let column = data.location_column;
+                    let style = theme.location;
+                    column.write(&mut writer, &location, style, true, true, url.as_deref())?;
+                }
+                Segment::Promoted => {
+                    self.format_promoted_fields(&mut writer, fmt_fields, prefix)?
+                }
+                Segment::Spans => {
+                    // the span context can only be printed once, as printing consumes it.
+                    self.format_span_context(&mut writer, fmt_fields, theme, scope.take())?;
+                }
+                Segment::Message => match message {
+                    Some(message) => writer.write_str(message)?,
+                    None => {
+                        let fields = Fields::Message;
+                        format_event_fields(fmt_fields, writer.by_ref(), event, style, fields)?
+                    }
+                },
+                Segment::Fields => {
+                    format_event_fields(fmt_fields, writer.by_ref(), event, style, Fields::Rest)?
+                }
+            }
+            skip_space = written.get() == before;
+        }
+        Ok(())
+    }
+}
+
+fn write_styled(writer: &mut Writer<'_>, value: &dyn fmt::Display, style: Style) -> fmt::Result {
+    write!(writer, "{}", Painted { value, style })
+}
+
+/// Returns the formatted fields of a span from its extensions, if it has any.
+///
+/// The formatted fields can be missing if the span was created before this layer was
+/// added (e.g. through `reload`) or if it was only enabled for other layers. Rather
+/// than panicking, the span is printed without fields.
+fn span_fields<'a, N: 'static>(ext: &'a Extensions<'_>) -> Option<&'a str> {
+    ext.get::<FormattedFields<N>>()
+        .filter(|fields| !fields.is_empty())
+        .map(|fields| fields.as_str())
+}
+
+/// Returns the separator between the fields formatted by `fmt_fields`.
+fn field_separator<N: 'static>(fmt_fields: &N) -> &'static str {
+    match (fmt_fields as &dyn Any).downcast_ref::<GlogFields>() {
+        Some(glog_fields) => glog_fields.config.separator(),
+        // `tracing-subscriber`'s default field formatter
+        None => " ",
+    }
+}
+
+/// Returns the message of a span lifecycle event: its kind, followed by the span's
+/// name and fields.
+fn lifecycle_message<S, N>(
+    span: &SpanRef<'_, S>,
+    lifecycle: &SpanLifecycle,
+    theme: &Theme,
+) -> String
+where
+    S: for<'a> LookupSpan<'a>,
+    N: 'static,
+{
+    let ext = span.extensions();
+    let fields = span_fields::<N>(&ext);
+    format!(
+        "{} {}",
+        Painted {
+            value: &lifecycle.label(),
+            style: theme.message,
+        },
+        FormatSpanFields::format_fields(span.name(), fields, theme, true)
+    )
+}
+
+impl Default for Glog<UtcTime> {
+    fn default() -> Self {
+        Glog {
+            timer: UtcTime::default(),
+            prefix: DefaultPrefix,
+            level_chars: Cow::Borrowed(&format::DEFAULT_FORMAT_LEVEL_CHARS),
+            with_thread_names: false,
+            with_target: false,
+            thread_name_column: Column::default(),
+            target_column: Column::default(),
+            file_path: FilePath::default(),
+            location_column: Column::default(),
+            with_span_context: true,
+            with_span_names: true,
+            span_events: FmtSpan::FULL,
+            #[cfg(feature = "tokio")]
+            with_tokio_tasks: false,
+            theme: Theme::default(),
+            with_line_coloring: false,
+            layout: None,
+            hyperlinks: None,
+            multiline: Multiline::default(),
+            max_span_context_len: None,
+            max_line_len: None,
+            span_selection: None,
+            duplicate_fields: DuplicateFields::default(),
+            promoted_fields: Vec::new(),
+        }
+    }
+}
+
+impl<S, N, T, P> FormatEvent<S, N> for Glog<T, P>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    T: FormatTime,
+    P: FormatPrefix<S>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> fmt::Result {
+        let lifecycle = SpanLifecycle::from_event(event);
+        if let Some(lifecycle) = &lifecycle {
+            let kind = lifecycle.kind().clone();
+            if self.span_events.clone() & kind.clone() != kind {
+                return Ok(());
+            }
+        }
+
+        let ansi = writer.has_ansi_escapes();
+        let plain;
+        let theme = if ansi {
+            &self.theme
+        } else {
+            plain = Theme::plain();
+            &plain
+        };
+
+        match self.max_line_len {
+            Some(max) => {
+                let mut truncate = Truncate::per_line(&mut writer, max);
+                let line = Writer::new(&mut truncate);
+                self.format_styled_line(ctx, line, theme, ansi, event, lifecycle)?;
+                truncate.finish()?;
+            }
+            None => self.format_styled_line(ctx, writer.by_ref(), theme, ansi, event, lifecycle)?,
+        }
+        writeln!(writer)
+    }
+}
+
+impl<T, P> Glog<T, P> {
+    /// Prints everything on an event's line but the trailing newline, coloring the
+    /// entire line by its severity if needed.
+    fn format_styled_line<S, N>(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        theme: &Theme,
+        ansi: bool,
+        event: &tracing::Event<'_>,
+        lifecycle: Option<SpanLifecycle>,
+    ) -> fmt::Result
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+        T: FormatTime,
+        P: FormatPrefix<S>,
+    {
+        let level = *event.metadata().level();
+        let keep_newlines = self.multiline != Multiline::Keep;
+        if ansi && self.with_line_coloring && matches!(level, Level::WARN | Level::ERROR) {
+            let base = theme.level(level);
+            let style = LineStyle {
+                ansi,
+                keep_newlines,
+                base: Some(base),
+            };
+            write!(writer, "{}", base.prefix())?;
+            let mut line = Restyle::new(&mut writer, base);
+            let theme = theme.layered_on(base);
+            self.format_line(ctx, Writer::new(&mut line), &theme, style, event, lifecycle)?;
+            write!(writer, "{}", base.suffix())
+        } else {
+            let style = LineStyle {
+                ansi,
+                keep_newlines,
+                base: None,
+            };
+            self.format_line(ctx, writer, theme, style, event, lifecycle)
+        }
+    }
+}
+
+/// How a line is styled, which its [`Writer`] can't always tell.
+#[derive(Clone, Copy)]
+struct LineStyle {
+    /// Whether ANSI escape codes are enabled.
+    ansi: bool,
+    /// Whether or not newlines in messages and fields are left for [`Continued`] to
+    /// handle, because the line isn't printed with [`Multiline::Keep`].
+    keep_newlines: bool,
+    /// The style of the entire line, if it is colored by its severity.
+    base: Option<Style>,
+}
+
+/// Which of an event's fields to format.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Fields {
+    All,
+    /// Only the message.
+    Message,
+    /// All fields but the message.
+    Rest,
+}
+
+/// Formats the fields of `event` selected by `fields` with `fmt_fields`.
+///
+/// Fields can only be selected when `fmt_fields` is [`GlogFields`]. Other formatters
+/// format all fields in place of the message, and nothing in place of the rest.
+///
+/// Only `tracing-subscriber` can enable ANSI escape codes on a [`Writer`], so when
+/// `fmt_fields` is [`GlogFields`], the fields are styled according to `style`
+/// instead. This allows them to be styled when written through an adapter such as
+/// [`Restyle`].
+fn format_event_fields<N>(
+    fmt_fields: &N,
+    writer: Writer<'_>,
+    event: &tracing::Event<'_>,
+    style: LineStyle,
+    fields: Fields,
+) -> fmt::Result
+where
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    match (fmt_fields as &dyn Any).downcast_ref::<GlogFields>() {
+        Some(glog_fields) => {
+            let mut config = glog_fields.config.clone();
+            if let Some(base) = style.base {
+                config.key_style = theme::layer(config.key_style, base);
+                config.message_style = theme::layer(config.message_style, base);
+                config.number_style = theme::layer(config.number_style, base);
+                config.string_style = theme::layer(config.string_style, base);
+                config.bool_style = theme::layer(config.bool_style, base);
+            }
+            let mut visitor = GlogVisitor::new(writer, config);
+            visitor.ansi = style.ansi;
+            visitor.keep_newlines = style.keep_newlines;
+            visitor.fields = fields;
+            event.record(&mut visitor);
+            visitor.finish()
+        }
+        None if fields == Fields::Rest => Ok(()),
+        None => fmt_fields.format_fields(writer, event),
+    }
+}
 
 #[derive(Clone)]
 struct FieldConfig {
     should_quote_strings: bool,
     use_whitespace_in_field: bool,
+    use_logfmt: bool,
+    key_style: Style,
+    message_style: Style,
+    number_style: Style,
+    string_style: Style,
+    bool_style: Style,
+    values: Arc<ValueFormat>,
+    error_format: Arc<ErrorFormat>,
+    error_backtraces: bool,
+    redaction: Option<Arc<Redaction>>,
+    escaping: Escaping,
+    max_field_len: Option<usize>,
+}
+
+impl FieldConfig {
+    fn separator(&self) -> &'static str {
+        if self.use_logfmt {
+            " "
+        } else {
+            ", "
+        }
+    }
 }
 
 impl Default for FieldConfig {
     fn default() -> Self {
+        let theme = Theme::default();
         Self {
             should_quote_strings: true,
             use_whitespace_in_field: true,
+            use_logfmt: false,
+            key_style: theme.field_key,
+            message_style: theme.message,
+            number_style: theme.number,
+            string_style: theme.string,
+            bool_style: theme.boolean,
+            values: Arc::default(),
+            error_format: Arc::default(),
+            error_backtraces: false,
+            redaction: None,
+            escaping: Escaping::default(),
+            max_field_len: None,
         }
     }
 }
@@ -413,26 +1516,182 @@ impl GlogFields {
     ///
     /// Setting `.compat()` will set [`GlogFields::use_whitespace_in_field`]
     /// and [`GlogFields::should_quote_strings`] to false.
+    ///
+    /// Values that would otherwise be ambiguous, such as strings containing `, `,
+    /// starting with a quote, or with unbalanced quotes or brackets, are still quoted,
+    /// so that fields recorded later with [`Span::record`] can replace them.
+    ///
+    /// [`Span::record`]: tracing::Span::record
     pub fn compact(self) -> Self {
         self.should_quote_strings(false)
             .use_whitespace_in_field(false)
     }
+
+    /// Sets whether or not fields are printed in [logfmt] style.
+    ///
+    /// When enabled, fields are printed as `key=value` and separated by a single
+    /// space. Values are only quoted when they would otherwise be ambiguous
+    /// (for instance, when they contain whitespace or `=`), and embedded `"` and
+    /// `\` are escaped, as are newlines, regardless of [`GlogFields::with_escaping`]
+    /// and [`Glog::with_multiline`]. The message is printed as a `msg` field, and the
+    /// sources of errors printed with [`ErrorFormat::Sources`] as a separate
+    /// `<field>.sources` field.
+    ///
+    /// The glog prefix is unaffected, and [`Glog`] still prints the span context
+    /// between the prefix and the message. To make the entire tail of each line
+    /// parseable by standard logfmt tooling, disable the span context with
+    /// [`Glog::with_span_context`], promoting any span fields that are needed with
+    /// [`Glog::with_promoted_fields`].
+    ///
+    /// This takes precedence over [`GlogFields::should_quote_strings`] and
+    /// [`GlogFields::use_whitespace_in_field`].
+    ///
+    /// # Example Output
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 examples/tokio.rs:38] [parent_task{subtasks=10 reason=testing}] msg="polling subtask" number=10 status="not ready"
+    /// </pre>
+    ///
+    /// [logfmt]: https://brandur.org/logfmt
+    pub fn use_logfmt(mut self, value: bool) -> Self {
+        self.config.use_logfmt = value;
+        self
+    }
+
+    /// Sets the formatter to print fields in [logfmt] style.
+    ///
+    /// This is equivalent to `.use_logfmt(true)`.
+    ///
+    /// [logfmt]: https://brandur.org/logfmt
+    pub fn logfmt(self) -> Self {
+        self.use_logfmt(true)
+    }
+
+    /// Sets the [`Theme`] used to style field names, field values and messages when
+    /// ANSI escape codes are enabled. Defaults to [`Theme::default`].
+    ///
+    /// The rest of the theme is used by [`Glog::with_theme`].
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.config.key_style = theme.field_key;
+        self.config.message_style = theme.message;
+        self.config.number_style = theme.number;
+        self.config.string_style = theme.string;
+        self.config.bool_style = theme.boolean;
+        self
+    }
+
+    /// Sets how typed field values, such as numbers and byte slices, are printed.
+    ///
+    /// By default, they are printed with their `Debug` implementations.
+    pub fn with_value_format(mut self, values: ValueFormat) -> Self {
+        self.config.values = Arc::new(values);
+        self
+    }
+
+    /// Sets how errors recorded as fields are printed. Defaults to
+    /// [`ErrorFormat::Sources`].
+    pub fn with_error_format(mut self, error_format: ErrorFormat) -> Self {
+        self.config.error_format = Arc::new(error_format);
+        self
+    }
+
+    /// Sets whether or not the backtraces of errors recorded as fields are printed, on
+    /// the lines following the error. Defaults to false.
+    ///
+    /// The backtrace is that of the first error in the source chain that provides one
+    /// through [`std::error::request_ref`], such as an `anyhow::Error`. This is only
+    /// available on nightly Rust, with `RUSTFLAGS="--cfg tracing_glog_unstable"`, since
+    /// stable Rust can't request backtraces from errors.
+    #[cfg(tracing_glog_unstable)]
+    pub fn with_error_backtraces(mut self, error_backtraces: bool) -> Self {
+        self.config.error_backtraces = error_backtraces;
+        self
+    }
+
+    /// Sets the [`Redaction`] rules applied to the fields of events and spans.
+    ///
+    /// By default, no fields are redacted.
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.config.redaction = Some(Arc::new(redaction));
+        self
+    }
+
+    /// Sets when control characters in messages and field values are escaped.
+    ///
+    /// Defaults to [`Escaping::Auto`], which escapes them when ANSI escape codes are
+    /// disabled.
+    pub fn with_escaping(mut self, escaping: Escaping) -> Self {
+        self.config.escaping = escaping;
+        self
+    }
+
+    /// Sets the maximum length of each field value, including the message, in bytes.
+    /// By default, there is no maximum.
+    ///
+    /// Longer values are cut and followed by a `...[truncated N bytes]` marker. Quoted
+    /// values keep their closing quote.
+    pub fn with_max_field_len(mut self, max_field_len: usize) -> Self {
+        self.config.max_field_len = Some(max_field_len);
+        self
+    }
 }
 
-impl<'a> MakeVisitor<Writer<'a>> for GlogFields {
-    type Visitor = GlogVisitor<'a>;
+impl<'writer> FormatFields<'writer> for GlogFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = GlogVisitor::new(writer, self.config.clone());
+        fields.record(&mut visitor);
+        visitor.finish()
+    }
+
+    /// Merges fields recorded after a span was created (for instance, with
+    /// [`Span::record`]) into the span's existing fields.
+    ///
+    /// The fields are separated in the same way as the rest of the span's fields,
+    /// and a field that was already recorded has its value replaced instead of
+    /// being printed a second time.
+    ///
+    /// [`Span::record`]: tracing::Span::record
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &Record<'_>,
+    ) -> fmt::Result {
+        // Render the new fields at the end of the existing ones so they pick up
+        // the same ANSI settings, then splice them back in one by one. Only the
+        // rendered fields are available here, but `GlogVisitor` quotes any value that
+        // wouldn't split exactly (see `is_self_contained`), so no field is cut.
+        let start = current.fields.len();
+        let mut visitor = GlogVisitor::new(current.as_writer(), self.config.clone());
+        fields.record(&mut visitor);
+        visitor.finish()?;
+        let added = current.fields.split_off(start);
 
-    #[inline]
-    fn make_visitor(&self, target: Writer<'a>) -> Self::Visitor {
-        GlogVisitor::new(target, self.config.clone())
+        let separator = self.config.separator();
+        let mut merged = split_fields(&current.fields, separator);
+        for field in split_fields(&added, separator) {
+            let existing = field_key(field).and_then(|key| {
+                merged
+                    .iter()
+                    .position(|other| field_key(other) == Some(key))
+            });
+            match existing {
+                Some(idx) => merged[idx] = field,
+                None => merged.push(field),
+            }
+        }
+        current.fields = merged.join(separator);
+        Ok(())
     }
 }
 
 #[doc(hidden)]
 pub struct GlogVisitor<'a> {
     writer: Writer<'a>,
+    ansi: bool,
+    /// Whether or not newlines are left for [`Continued`] to handle, instead of being
+    /// escaped.
+    keep_newlines: bool,
+    fields: Fields,
     is_empty: bool,
-    style: Style,
     result: fmt::Result,
     config: FieldConfig,
 }
@@ -440,9 +1699,11 @@ pub struct GlogVisitor<'a> {
 impl<'a> GlogVisitor<'a> {
     fn new(writer: Writer<'a>, config: FieldConfig) -> Self {
         Self {
+            ansi: writer.has_ansi_escapes(),
+            keep_newlines: false,
+            fields: Fields::All,
             writer,
             is_empty: true,
-            style: Style::new(),
             result: Ok(()),
             config,
         }
@@ -453,64 +1714,269 @@ impl<'a> GlogVisitor<'a> {
             self.is_empty = false;
             ""
         } else {
-            ", "
+            self.config.separator()
         };
         self.result = write!(self.writer, "{padding}{value:?}");
     }
 
-    fn write_field(&mut self, name: &str, value: &dyn fmt::Debug) {
-        let bold = self.bold();
+    /// Writes a field. Unless `structured` is set, ambiguous values are quoted, so that
+    /// they can be told apart from the fields that follow them (see [`split_fields`]).
+    fn write_field(
+        &mut self,
+        name: &str,
+        value: &dyn fmt::Debug,
+        style: Style,
+        escape: Escape,
+        structured: bool,
+    ) {
+        let max = self.config.max_field_len;
+        let value = &Truncated { value, max };
+        let key = self.style(self.config.key_style);
+        let style = self.style(style);
+        if self.config.use_logfmt {
+            let value = format!("{value:?}");
+            self.write_padded(&format_args!(
+                "{}{}{}={}{}{}",
+                key.prefix(),
+                name,
+                key.suffix(),
+                style.prefix(),
+                LogfmtValue(&value, escape),
+                style.suffix(),
+            ));
+            return;
+        }
+
+        // whether to quote is decided from the escaped value, which is what
+        // `split_fields` sees, but the raw value is quoted, so it's only escaped once.
+        let raw = value;
+        let mut value = format!("{:?}", Escaped { value: raw, escape });
+        if !structured && !is_self_contained(&value, self.config.separator()) {
+            let raw = format!("{raw:?}");
+            value = Quoted {
+                value: &raw,
+                escape,
+            }
+            .to_string();
+        }
         if self.config.use_whitespace_in_field {
             self.write_padded(&format_args!(
-                "{}{}{}: {:?}",
-                bold.prefix(),
+                "{}{}{}: {}{}{}",
+                key.prefix(),
                 name,
-                bold.infix(self.style),
+                key.suffix(),
+                style.prefix(),
                 value,
+                style.suffix(),
             ));
         } else {
             self.write_padded(&format_args!(
-                "{}{}{}:{:?}",
-                bold.prefix(),
+                "{}{}{}:{}{}{}",
+                key.prefix(),
                 name,
-                bold.infix(self.style),
+                key.suffix(),
+                style.prefix(),
                 value,
+                style.suffix(),
             ));
         }
     }
 
-    fn bold(&self) -> Style {
-        if self.writer.has_ansi_escapes() {
-            self.style.bold()
+    fn write_message(&mut self, value: &dyn fmt::Debug, escape: Escape) {
+        let max = self.config.max_field_len;
+        let value = &Truncated { value, max };
+        let style = self.style(self.config.message_style);
+        self.write_padded(&format_args!(
+            "{}{:?}{}",
+            style.prefix(),
+            Escaped { value, escape },
+            style.suffix()
+        ));
+    }
+
+    fn style(&self, style: Style) -> Style {
+        if self.ansi {
+            style
         } else {
             Style::new()
         }
     }
 }
 
+impl<'a> GlogVisitor<'a> {
+    /// Returns the replacement for `value` if the field should be redacted.
+    fn redact(&self, field: &Field, value: &dyn fmt::Display) -> Option<redact::Redacted> {
+        let redaction = self.config.redaction.as_ref()?;
+        redaction.redact(field_name(field), value)
+    }
+
+    /// Writes the value of `field`, which is styled in `style` unless it's the message.
+    fn write_value(&mut self, field: &Field, value: &dyn fmt::Debug, style: Style) {
+        let escape = self.escape();
+        self.write_value_escaped(field, value, style, escape, false)
+    }
+
+    /// Returns which characters of values are escaped.
+    ///
+    /// Newlines are left unescaped when a [`Multiline`] mode other than
+    /// [`Multiline::Keep`] handles them.
+    fn escape(&self) -> Escape {
+        match (self.config.escaping.escapes(self.ansi), self.keep_newlines) {
+            (false, _) => Escape::Nothing,
+            (true, true) => Escape::AllButNewlines,
+            (true, false) => Escape::All,
+        }
+    }
+
+    /// Writes the value of `field` like [`GlogVisitor::write_value`], escaping the
+    /// characters selected by `escape`. If `structured` is set, the value is never
+    /// quoted, as for an error followed by its sources.
+    fn write_value_escaped(
+        &mut self,
+        field: &Field,
+        value: &dyn fmt::Debug,
+        style: Style,
+        escape: Escape,
+        structured: bool,
+    ) {
+        let selected = match self.fields {
+            Fields::All => true,
+            Fields::Message => field.name() == "message",
+            Fields::Rest => field.name() != "message",
+        };
+        if !selected {
+            return;
+        }
+
+        match field.name() {
+            "message" if self.config.use_logfmt => {
+                let style = self.config.message_style;
+                self.write_field("msg", value, style, escape, false)
+            }
+            "message" => self.write_message(value, escape),
+            // Skip fields that are actually log metadata that have already been handled
+            name if name.starts_with("log.") => self.result = Ok(()),
+            _ => self.write_field(field_name(field), value, style, escape, structured),
+        };
+    }
+}
+
+impl<'a> GlogVisitor<'a> {
+    /// Writes a typed value, or its replacement if the field should be redacted.
+    fn record_typed(&mut self, field: &Field, value: &dyn fmt::Display, style: Style) {
+        if self.result.is_err() {
+            return;
+        }
+
+        match self.redact(field, value) {
+            Some(redacted) => self.write_value(field, &redacted, Style::new()),
+            None => self.write_value(field, &format_args!("{value}"), style),
+        }
+    }
+
+    fn record_integer(&mut self, field: &Field, negative: bool, magnitude: u128) {
+        let values = self.config.values.clone();
+        let value = values.integer(field_name(field), negative, magnitude);
+        self.record_typed(field, &value, self.config.number_style)
+    }
+}
+
 impl<'a> Visit for GlogVisitor<'a> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let value = self.config.values.float(value);
+        self.record_typed(field, &value, self.config.number_style)
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_integer(field, value < 0, value.unsigned_abs().into())
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record_integer(field, false, value.into())
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.record_integer(field, value < 0, value.unsigned_abs())
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.record_integer(field, false, value)
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record_typed(field, &value, self.config.bool_style)
+    }
+
+    fn record_bytes(&mut self, field: &Field, value: &[u8]) {
+        let values = self.config.values.clone();
+        self.record_typed(field, &values.bytes(value), Style::new())
+    }
+
     fn record_str(&mut self, field: &Field, value: &str) {
         if self.result.is_err() {
             return;
         }
 
-        if field.name() == "message" {
-            self.record_debug(field, &format_args!("{value}"))
-        } else if self.config.should_quote_strings {
-            self.record_debug(field, &value)
+        let style = self.config.string_style;
+        if let Some(redacted) = self.redact(field, &value) {
+            self.write_value(field, &redacted, Style::new())
+        } else if field.name() == "message" || self.config.use_logfmt {
+            self.write_value(field, &format_args!("{value}"), style)
+        } else if self.config.should_quote_strings || value.starts_with('"') {
+            // a string starting with a quote is quoted so it isn't mistaken for one
+            // that was quoted, such as by `Glog::with_promoted_fields`.
+            self.write_value(field, &value, style)
         } else {
-            self.record_debug(field, &format_args!("{value}"))
+            self.write_value(field, &format_args!("{value}"), style)
         }
     }
 
     fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
-        if let Some(source) = value.source() {
-            self.record_debug(
-                field,
-                &format_args!("{}, {}.sources: {}", value, field, ErrorSourceList(source),),
-            )
-        } else {
-            self.record_debug(field, &format_args!("{value}"))
+        if self.result.is_err() {
+            return;
+        }
+
+        let redaction = self.config.redaction.clone();
+        let error_format = self.config.error_format.clone();
+        let redaction = redaction.as_deref();
+        if let Some(redacted) = redaction.and_then(|r| r.redact(field_name(field), value)) {
+            return self.write_value(field, &redacted, Style::new());
+        }
+
+        // logfmt has no lists of fields, so the sources are printed as a field of their
+        // own, after the error.
+        let logfmt_sources = self.config.use_logfmt && *error_format == ErrorFormat::Sources;
+        let format = match logfmt_sources {
+            true => &ErrorFormat::TopLevel,
+            false => &*error_format,
+        };
+        let mut error = FormatError {
+            error: value,
+            field,
+            format,
+            redaction,
+            backtraces: self.config.error_backtraces,
+            escape: Escape::Nothing,
+        };
+        // the newlines of multi-line errors aren't escaped, only the errors themselves.
+        let (escape, multiline) = (self.escape(), error.is_multiline());
+        let value_escape = match multiline {
+            true => {
+                error.escape = escape;
+                Escape::Nothing
+            }
+            false => escape,
+        };
+        // errors are printed in the shape that their format gives them, which may look
+        // like several fields or span several lines, so they aren't quoted.
+        let value = format_args!("{error}");
+        self.write_value_escaped(field, &value, Style::new(), value_escape, true);
+
+        let sources = error.sources().filter(|_| logfmt_sources);
+        if let Some(sources) = sources.filter(|_| self.fields != Fields::Message) {
+            let name = format!("{}.sources", field_name(field));
+            let sources = format_args!("{sources}");
+            self.write_field(&name, &sources, Style::new(), escape, true);
         }
     }
 
@@ -519,19 +1985,25 @@ impl<'a> Visit for GlogVisitor<'a> {
             return;
         }
 
-        match field.name() {
-            "message" => self.write_padded(&format_args!("{}{:?}", self.style.prefix(), value,)),
-            // Skip fields that are actually log metadata that have already been handled
-            name if name.starts_with("log.") => self.result = Ok(()),
-            name if name.starts_with("r#") => self.write_field(&name[2..], value),
-            name => self.write_field(name, value),
-        };
+        let values = self.config.values.clone();
+        if let Some(duration) = values.duration(field, value) {
+            return self.record_typed(field, &duration, self.config.number_style);
+        }
+        match self.redact(field, &format_args!("{value:?}")) {
+            Some(redacted) => self.write_value(field, &redacted, Style::new()),
+            None => self.write_value(field, value, Style::new()),
+        }
     }
 }
 
+/// Returns the name of `field`, without the `r#` prefix of raw identifiers.
+fn field_name(field: &Field) -> &'static str {
+    let name = field.name();
+    name.strip_prefix("r#").unwrap_or(name)
+}
+
 impl<'a> VisitOutput<fmt::Result> for GlogVisitor<'a> {
-    fn finish(mut self) -> fmt::Result {
-        write!(&mut self.writer, "{}", self.style.suffix())?;
+    fn finish(self) -> fmt::Result {
         self.result
     }
 }
@@ -542,22 +2014,223 @@ impl<'a> VisitFmt for GlogVisitor<'a> {
     }
 }
 
-/// Renders an error into a list of sources, *including* the error
-struct ErrorSourceList<'a>(&'a (dyn std::error::Error + 'static));
+/// Splits rendered fields on `separator`, ignoring any separators that appear
+/// inside quoted strings, nested `Debug` output or ANSI escape sequences.
+fn split_fields<'a>(fields: &'a str, separator: &str) -> Vec<&'a str> {
+    scan_fields(fields, separator).0
+}
+
+/// Returns whether or not `value` can be printed as is in a field, so that
+/// [`split_fields`] splits it from the fields around it exactly.
+///
+/// This isn't the case for unquoted values that contain `separator`, a quote or
+/// unbalanced brackets, which are quoted instead.
+fn is_self_contained(value: &str, separator: &str) -> bool {
+    let (segments, balanced) = scan_fields(value, separator);
+    segments.len() <= 1 && balanced
+}
+
+/// Splits `fields` like [`split_fields`], also returning whether or not all quotes and
+/// brackets were closed.
+fn scan_fields<'a>(fields: &'a str, separator: &str) -> (Vec<&'a str>, bool) {
+    let mut segments = Vec::new();
+    if fields.is_empty() {
+        return (segments, true);
+    }
+
+    let bytes = fields.as_bytes();
+    let (mut start, mut i, mut depth, mut quoted) = (0, 0, 0usize, false);
+    let mut balanced = true;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\x1b' => {
+                i = skip_ansi_escape(bytes, i);
+                continue;
+            }
+            b'\\' if quoted => i += 1,
+            b'"' => quoted = !quoted,
+            b'(' | b'[' | b'{' if !quoted => depth += 1,
+            b')' | b']' | b'}' if !quoted => match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => balanced = false,
+            },
+            _ if !quoted && depth == 0 && bytes[i..].starts_with(separator.as_bytes()) => {
+                segments.push(&fields[start..i]);
+                i += separator.len();
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    segments.push(&fields[start..]);
+    (segments, balanced && depth == 0 && !quoted)
+}
+
+/// Returns the name of a single rendered field, or `None` if it has no name
+/// (for instance, if it is a message).
+fn field_key(field: &str) -> Option<&str> {
+    let field = strip_leading_ansi(field);
+    let end = field
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(field.len());
+    let (key, rest) = field.split_at(end);
+    let rest = strip_leading_ansi(rest);
+    if !key.is_empty() && (rest.starts_with(':') || rest.starts_with('=')) {
+        Some(key)
+    } else {
+        None
+    }
+}
 
-impl<'a> fmt::Display for ErrorSourceList<'a> {
+/// Returns the value of the field named `key` from fields rendered by `GlogFields` or
+/// by `tracing-subscriber`'s default field formatter, without quotes or styling.
+///
+/// `separator` is the separator between fields, see [`field_separator`].
+fn field_value<'a>(fields: &'a str, key: &str, separator: &str) -> Option<Cow<'a, str>> {
+    let field = split_fields(fields, separator)
+        .into_iter()
+        .find(|field| field_key(field) == Some(key))?;
+    let value = strip_leading_ansi(field);
+    let value = strip_leading_ansi(&value[key.len()..]);
+    let value = value[1..].strip_prefix(' ').unwrap_or(&value[1..]);
+    let value = strip_leading_ansi(value);
+    let value = value.strip_suffix("\x1b[0m").unwrap_or(value);
+    Some(unquote(value).map_or(Cow::Borrowed(value), Cow::Owned))
+}
+
+/// Returns the string that `value` is the quoted form of, or `None` if it isn't a
+/// single quoted string.
+///
+/// This undoes the escapes of both `Debug` and logfmt, such as `\"`, `\n` and
+/// `\u{1b}`.
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '"' => return None,
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'u' => {
+                    let rest = chars.as_str().strip_prefix('{')?;
+                    let (hex, rest) = rest.split_once('}')?;
+                    chars = rest.chars();
+                    char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+                }
+                c => c,
+            },
+            c => c,
+        };
+        unquoted.push(c);
+    }
+    Some(unquoted)
+}
+
+fn strip_leading_ansi(mut s: &str) -> &str {
+    while s.starts_with('\x1b') {
+        s = &s[skip_ansi_escape(s.as_bytes(), 0)..];
+    }
+    s
+}
+
+/// Returns the index just past the ANSI escape sequence starting at `start`.
+fn skip_ansi_escape(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    if bytes.get(i) == Some(&b'[') {
+        i += 1;
+        while i < bytes.len() && !(0x40..=0x7e).contains(&bytes[i]) {
+            i += 1;
+        }
+    }
+    (i + 1).min(bytes.len())
+}
+
+/// Renders a value for logfmt output, quoting it only when necessary.
+///
+/// The characters selected by the second field are escaped inside the quotes, as are
+/// newlines, so that each line can be parsed on its own.
+struct LogfmtValue<'a>(&'a str, Escape);
+
+impl<'a> LogfmtValue<'a> {
+    fn needs_quotes(&self) -> bool {
+        self.0.is_empty()
+            || self.0.chars().any(|c| {
+                c.is_whitespace() || escape::needs_escape(c) || matches!(c, '=' | '"' | '\\')
+            })
+            || !is_self_contained(self.0, " ")
+    }
+}
+
+impl<'a> fmt::Display for LogfmtValue<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut list = f.debug_list();
-        let mut curr = Some(self.0);
-        while let Some(curr_err) = curr {
-            list.entry(&format_args!("{curr_err}"));
-            curr = curr_err.source();
+        match self.needs_quotes() {
+            true => write!(
+                f,
+                "{}",
+                Quoted {
+                    value: self.0,
+                    escape: self.1.with_newlines()
+                }
+            ),
+            false => f.write_str(self.0),
         }
-        list.finish()
     }
 }
 
+/// Returns the name of the tokio task from its `runtime.spawn` span, which tokio
+/// creates for each task when its instrumentation is enabled.
+#[cfg(feature = "tokio")]
+fn tokio_task_name<S, N>(scope: Scope<'_, S>, fmt_fields: &N) -> Option<String>
+where
+    S: for<'a> LookupSpan<'a>,
+    N: 'static,
+{
+    let span = scope.from_root().find(|span| {
+        span.name() == "runtime.spawn" && span.metadata().target().starts_with("tokio::task")
+    })?;
+    let ext = span.extensions();
+    let fields = ext.get::<FormattedFields<N>>()?;
+    field_value(fields, "task.name", field_separator(fmt_fields))
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
 #[inline(always)]
 fn get_pid() -> u32 {
     std::process::id()
 }
+
+/// Returns the numeric ID of the current thread, as assigned by the kernel.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn get_tid() -> u64 {
+    thread_local! {
+        // SAFETY: `gettid` takes no arguments and always succeeds.
+        static TID: u64 = unsafe { libc::syscall(libc::SYS_gettid) } as u64;
+    }
+    TID.with(|tid| *tid)
+}
+
+/// Returns the numeric ID of the current thread, as assigned by the Rust standard
+/// library.
+///
+/// `ThreadId::as_u64` is unstable, so this is read from the ID's `Debug` output
+/// (`ThreadId(N)`) once per thread.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn get_tid() -> u64 {
+    thread_local! {
+        static TID: u64 = {
+            let id = format!("{:?}", std::thread::current().id());
+            id.trim_start_matches("ThreadId(")
+                .trim_end_matches(')')
+                .parse()
+                .unwrap_or(0)
+        };
+    }
+    TID.with(|tid| *tid)
+}
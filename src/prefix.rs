@@ -0,0 +1,176 @@
+use crate::{
+    format::{FmtLevel, FormatProcessData},
+    theme::Theme,
+};
+use std::{fmt, thread::ThreadId};
+use tracing::{Level, Metadata};
+use tracing_subscriber::{
+    fmt::{format::Writer, time::FormatTime},
+    registry::{LookupSpan, Scope, SpanRef},
+};
+
+/// Formats the prefix of each line, which precedes the span context and the fields.
+///
+/// This is the equivalent of glog's `InstallPrefixFormatter`. An implementation is
+/// installed with [`Glog::with_prefix_formatter`], and receives a [`Prefix`]
+/// describing the event. It writes everything up to the span context, including the
+/// separator between the two: [`DefaultPrefix`] ends the prefix with `] `.
+///
+/// This trait is implemented for closures, so a custom prefix can be as short as:
+///
+/// ```
+/// use std::fmt;
+/// use tracing_glog::{Glog, GlogFields, Prefix};
+/// use tracing_subscriber::{fmt::format::Writer, Registry};
+///
+/// let glog = Glog::default().with_prefix_formatter(
+///     |writer: &mut Writer<'_>, prefix: &Prefix<'_, Registry>| -> fmt::Result {
+///         let file = prefix.file().unwrap_or("<unknown>");
+///         let line = prefix.line().unwrap_or(0);
+///         write!(writer, "{} {}:{}] ", prefix.level(), file, line)
+///     },
+/// );
+///
+/// tracing_subscriber::fmt()
+///     .event_format(glog)
+///     .fmt_fields(GlogFields::default())
+///     .init();
+/// ```
+///
+/// [`Glog::with_prefix_formatter`]: crate::Glog::with_prefix_formatter
+pub trait FormatPrefix<S>
+where
+    S: for<'a> LookupSpan<'a>,
+{
+    /// Writes the prefix of the line described by `prefix` to `writer`.
+    fn format_prefix(&self, writer: &mut Writer<'_>, prefix: &Prefix<'_, S>) -> fmt::Result;
+}
+
+/// glog's own prefix, configured by the methods of [`Glog`]. This is the default
+/// [`FormatPrefix`].
+///
+/// [`Glog`]: crate::Glog
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultPrefix;
+
+impl<S> FormatPrefix<S> for DefaultPrefix
+where
+    S: for<'a> LookupSpan<'a>,
+{
+    fn format_prefix(&self, writer: &mut Writer<'_>, prefix: &Prefix<'_, S>) -> fmt::Result {
+        prefix.format_default(writer)
+    }
+}
+
+impl<S, F> FormatPrefix<S> for F
+where
+    S: for<'a> LookupSpan<'a>,
+    F: Fn(&mut Writer<'_>, &Prefix<'_, S>) -> fmt::Result,
+{
+    fn format_prefix(&self, writer: &mut Writer<'_>, prefix: &Prefix<'_, S>) -> fmt::Result {
+        (self)(writer, prefix)
+    }
+}
+
+/// A description of the event whose prefix is being formatted by a [`FormatPrefix`].
+pub struct Prefix<'a, S>
+where
+    S: for<'l> LookupSpan<'l>,
+{
+    pub(crate) level: FmtLevel<'a>,
+    pub(crate) timer: &'a dyn FormatTime,
+    pub(crate) data: FormatProcessData<'a>,
+    pub(crate) span: Option<SpanRef<'a, S>>,
+}
+
+impl<'a, S> Prefix<'a, S>
+where
+    S: for<'l> LookupSpan<'l>,
+{
+    /// Returns the event's level.
+    pub fn level(&self) -> Level {
+        *self.data.metadata.level()
+    }
+
+    /// Returns the label of the event's level, as configured with
+    /// [`Glog::with_format_level_chars`].
+    ///
+    /// [`Glog::with_format_level_chars`]: crate::Glog::with_format_level_chars
+    pub fn level_label(&self) -> &str {
+        self.level.label
+    }
+
+    /// Writes the current time with the timer configured with [`Glog::with_timer`].
+    ///
+    /// [`Glog::with_timer`]: crate::Glog::with_timer
+    pub fn write_timestamp(&self, writer: &mut Writer<'_>) -> fmt::Result {
+        self.timer.format_time(writer)
+    }
+
+    /// Returns the ID of the current process.
+    pub fn pid(&self) -> u32 {
+        self.data.pid
+    }
+
+    /// Returns the ID of the current thread.
+    pub fn thread_id(&self) -> ThreadId {
+        std::thread::current().id()
+    }
+
+    /// Returns the name of the current thread, if it has one.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.data.thread_name
+    }
+
+    /// Returns the event's metadata. For events converted from `log` records, this is
+    /// the metadata of the record.
+    pub fn metadata(&self) -> &Metadata<'a> {
+        self.data.metadata
+    }
+
+    /// Returns the event's target.
+    pub fn target(&self) -> &str {
+        self.data.metadata.target()
+    }
+
+    /// Returns the path of the source file that recorded the event, as recorded in its
+    /// metadata.
+    pub fn file(&self) -> Option<&str> {
+        self.data.metadata.file()
+    }
+
+    /// Returns the line of the source file that recorded the event.
+    pub fn line(&self) -> Option<u32> {
+        self.data.metadata.line()
+    }
+
+    /// Returns the spans the event is in, from the innermost span to the root.
+    pub fn scope(&self) -> Option<Scope<'a, S>> {
+        self.span.as_ref().map(|span| span.scope())
+    }
+
+    /// Returns the ID of the current tokio task, if [`Glog::with_tokio_tasks`] is
+    /// enabled and the event was recorded in a task.
+    ///
+    /// [`Glog::with_tokio_tasks`]: crate::Glog::with_tokio_tasks
+    #[cfg(feature = "tokio")]
+    pub fn tokio_task(&self) -> Option<tokio::task::Id> {
+        self.data.task.as_ref().map(|task| task.id)
+    }
+
+    /// Returns the theme of the line. When ANSI escape codes are disabled, this is
+    /// [`Theme::plain`].
+    pub fn theme(&self) -> &Theme {
+        self.data.theme
+    }
+
+    /// Writes the prefix of [`DefaultPrefix`], which custom prefixes can extend.
+    pub fn format_default(&self, writer: &mut Writer<'_>) -> fmt::Result {
+        let theme = self.data.theme;
+        write!(writer, "{}", self.level)?;
+        write!(writer, "{}", theme.timestamp.prefix())?;
+        self.timer.format_time(writer)?;
+        write!(writer, "{}", theme.timestamp.suffix())?;
+        write!(writer, "{}] ", self.data)
+    }
+}
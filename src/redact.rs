@@ -0,0 +1,213 @@
+use std::fmt;
+
+/// Rules for redacting the values of sensitive fields, such as tokens and passwords.
+///
+/// A field is redacted if its name is one of the given names, if its name matches one
+/// of the given glob patterns, or, with the `regex` feature, if its value matches one
+/// of the given regular expressions. Names are matched case-insensitively.
+///
+/// Redacted values are replaced with `<redacted>`, or, with a key, with a short keyed
+/// hash of the value such as `<redacted:3f2a9c1b>`, so that lines with the same value
+/// can still be correlated without revealing it.
+///
+/// The rules are applied by [`GlogFields`] to the fields of both events and spans, and
+/// to each error in the source chain of errors recorded as fields.
+///
+/// ```
+/// use tracing_glog::{GlogFields, Redaction};
+///
+/// let redaction = Redaction::default()
+///     .with_name("password")
+///     .with_name("authorization")
+///     .with_glob("*token*")
+///     .with_keyed_hash(*b"an example key!!");
+/// let fields = GlogFields::default().with_redaction(redaction);
+/// ```
+///
+/// [`GlogFields`]: crate::GlogFields
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+    names: Vec<String>,
+    globs: Vec<String>,
+    #[cfg(feature = "regex")]
+    values: Vec<regex::Regex>,
+    key: Option<[u8; 16]>,
+}
+
+impl Redaction {
+    /// Redacts fields named `name`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    /// Redacts fields whose name matches `glob`, in which `*` matches any number of
+    /// characters and `?` matches a single character.
+    pub fn with_glob(mut self, glob: impl Into<String>) -> Self {
+        self.globs.push(glob.into());
+        self
+    }
+
+    /// Redacts fields whose value matches `pattern`.
+    ///
+    /// Values are matched as they would be printed, without quotes. Messages are
+    /// values too, so they are redacted entirely if they match.
+    #[cfg(feature = "regex")]
+    pub fn with_value_pattern(mut self, pattern: regex::Regex) -> Self {
+        self.values.push(pattern);
+        self
+    }
+
+    /// Replaces redacted values with a hash of the value keyed with `key`, instead of
+    /// `<redacted>`.
+    ///
+    /// The hash is short, so it only distinguishes between a limited number of values.
+    /// `key` should be kept secret, since anyone with the key can test guesses of
+    /// low-entropy values, such as passwords, against their hash.
+    pub fn with_keyed_hash(mut self, key: [u8; 16]) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Returns whether or not the field named `name` is always redacted.
+    fn redacts_name(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n.eq_ignore_ascii_case(name))
+            || self.globs.iter().any(|glob| glob_matches(glob, name))
+    }
+
+    /// Returns the replacement for the field named `name` with `value`, if it should
+    /// be redacted.
+    pub(crate) fn redact(&self, name: &str, value: &dyn fmt::Display) -> Option<Redacted> {
+        if self.redacts_name(name) {
+            return Some(self.replacement(|| value.to_string()));
+        }
+        self.redact_value(value)
+    }
+
+    /// Returns the replacement for `value` if it matches one of the value patterns.
+    #[cfg(feature = "regex")]
+    pub(crate) fn redact_value(&self, value: &dyn fmt::Display) -> Option<Redacted> {
+        if self.values.is_empty() {
+            return None;
+        }
+        let value = value.to_string();
+        if self.values.iter().any(|pattern| pattern.is_match(&value)) {
+            Some(self.replacement(|| value))
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(feature = "regex"))]
+    pub(crate) fn redact_value(&self, _value: &dyn fmt::Display) -> Option<Redacted> {
+        None
+    }
+
+    fn replacement(&self, value: impl FnOnce() -> String) -> Redacted {
+        Redacted {
+            hash: self
+                .key
+                .map(|key| (siphash24(&key, value().as_bytes()) >> 32) as u32),
+        }
+    }
+}
+
+/// The replacement for a redacted value.
+pub(crate) struct Redacted {
+    hash: Option<u32>,
+}
+
+impl fmt::Display for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hash {
+            Some(hash) => write!(f, "<redacted:{hash:08x}>"),
+            None => f.write_str("<redacted>"),
+        }
+    }
+}
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Matches `name` against `glob`, ignoring ASCII case.
+pub(crate) fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // the position of the last `*` in `glob`, and of `name` when it was reached.
+    let mut backtrack = None;
+    let (mut g, mut n) = (0, 0);
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&name[n]) => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` match one more character.
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    g = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// SipHash-2-4, which is keyed, unlike `std`'s `DefaultHasher`.
+fn siphash24(key: &[u8; 16], data: &[u8]) -> u64 {
+    let k0 = u64::from_le_bytes(key[..8].try_into().unwrap());
+    let k1 = u64::from_le_bytes(key[8..].try_into().unwrap());
+    let mut v = [
+        k0 ^ 0x736f6d6570736575,
+        k1 ^ 0x646f72616e646f6d,
+        k0 ^ 0x6c7967656e657261,
+        k1 ^ 0x7465646279746573,
+    ];
+    let round = |v: &mut [u64; 4]| {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    };
+
+    let mut chunks = data.chunks_exact(8);
+    let compress = |v: &mut [u64; 4], m: u64| {
+        v[3] ^= m;
+        round(v);
+        round(v);
+        v[0] ^= m;
+    };
+    for chunk in &mut chunks {
+        compress(&mut v, u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    let last = chunks
+        .remainder()
+        .iter()
+        .enumerate()
+        .fold((data.len() as u64) << 56, |last, (i, &byte)| {
+            last | u64::from(byte) << (8 * i)
+        });
+    compress(&mut v, last);
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use tracing::Metadata;
+
+/// Selects which of an event's spans are printed in its span context.
+///
+/// Spans can be included or excluded by name or by target. Targets match spans whose
+/// target is the given target or one of its submodules, so `hyper` matches spans from
+/// `hyper::proto::h1`. If any spans are included, only those are printed, except for
+/// any that are also excluded.
+///
+/// Of the remaining spans, only the innermost ones can be kept with
+/// [`SpanSelection::with_innermost`], and deeply nested spans can be elided with
+/// [`SpanSelection::with_max_depth`].
+///
+/// ```
+/// use tracing_glog::{Glog, SpanSelection};
+///
+/// let selection = SpanSelection::default()
+///     .exclude_target("tower")
+///     .exclude_target("hyper")
+///     .with_max_depth(3);
+/// let glog = Glog::default().with_span_selection(selection);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SpanSelection {
+    include: Vec<SpanMatch>,
+    exclude: Vec<SpanMatch>,
+    innermost: Option<usize>,
+    max_depth: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+enum SpanMatch {
+    Name(String),
+    Target(String),
+}
+
+impl SpanMatch {
+    fn matches(&self, metadata: &Metadata<'_>) -> bool {
+        match self {
+            SpanMatch::Name(name) => metadata.name() == name,
+            SpanMatch::Target(target) => metadata
+                .target()
+                .strip_prefix(target.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::")),
+        }
+    }
+}
+
+impl SpanSelection {
+    /// Includes spans named `name`.
+    pub fn include_name(mut self, name: impl Into<String>) -> Self {
+        self.include.push(SpanMatch::Name(name.into()));
+        self
+    }
+
+    /// Includes spans from `target` and its submodules.
+    pub fn include_target(mut self, target: impl Into<String>) -> Self {
+        self.include.push(SpanMatch::Target(target.into()));
+        self
+    }
+
+    /// Excludes spans named `name`.
+    pub fn exclude_name(mut self, name: impl Into<String>) -> Self {
+        self.exclude.push(SpanMatch::Name(name.into()));
+        self
+    }
+
+    /// Excludes spans from `target` and its submodules.
+    pub fn exclude_target(mut self, target: impl Into<String>) -> Self {
+        self.exclude.push(SpanMatch::Target(target.into()));
+        self
+    }
+
+    /// Only prints the `innermost` spans closest to the event. With `1`, only the span
+    /// the event is in is printed.
+    pub fn with_innermost(self, innermost: usize) -> Self {
+        Self {
+            innermost: Some(innermost),
+            ..self
+        }
+    }
+
+    /// Prints at most `max_depth` spans. Deeper span contexts keep their root and their
+    /// innermost spans, with the spans in between elided as `...`:
+    ///
+    /// <pre>
+    /// I0731 16:23:45.674465 990039 examples/yak-shave.rs:56] [request{id: 7}, ..., shave{yak: 3}] could not locate yak
+    /// </pre>
+    ///
+    /// With `1`, only the innermost span is kept, after the `...`. With `0`, the span
+    /// context is printed as `[...]` when there are any spans.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+            ..self
+        }
+    }
+
+    /// Returns whether or not the span described by `metadata` is selected.
+    pub(crate) fn selects(&self, metadata: &Metadata<'_>) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|m| m.matches(metadata)))
+            && !self.exclude.iter().any(|m| m.matches(metadata))
+    }
+
+    /// Returns the indices of the printed spans out of `len` selected spans, from the
+    /// root, with `None` where spans are elided.
+    pub(crate) fn visible(&self, len: usize) -> Vec<Option<usize>> {
+        let start = self
+            .innermost
+            .map_or(0, |innermost| len.saturating_sub(innermost));
+        match self.max_depth {
+            Some(max_depth) if len - start > max_depth => {
+                let mut visible = Vec::with_capacity(max_depth + 1);
+                let innermost = if max_depth >= 2 {
+                    visible.push(Some(start));
+                    max_depth - 1
+                } else {
+                    max_depth
+                };
+                visible.push(None);
+                visible.extend((len - innermost..len).map(Some));
+                visible
+            }
+            _ => (start..len).map(Some).collect(),
+        }
+    }
+}
+
+/// Which value of a field is printed when several spans in the span context have a
+/// field with the same name, such as a `request_id` passed down through
+/// `#[instrument]`ed functions.
+///
+/// Defaults to [`DuplicateFields::Keep`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateFields {
+    /// Every span's fields are printed.
+    #[default]
+    Keep,
+    /// Only the value from the innermost span with the field is printed.
+    Innermost,
+    /// Only the value from the outermost span with the field is printed.
+    Outermost,
+}
+
+impl DuplicateFields {
+    /// Removes the fields of `spans`, listed from the root, whose names are repeated by
+    /// the field that wins. Fields are named by `key`.
+    ///
+    /// Within a span, the field recorded last wins, since some field formatters append
+    /// the fields recorded with `Span::record` instead of replacing them.
+    pub(crate) fn dedup<'a>(self, spans: &mut [Vec<&'a str>], key: fn(&'a str) -> Option<&'a str>) {
+        let order: Vec<usize> = match self {
+            DuplicateFields::Keep => return,
+            DuplicateFields::Innermost => (0..spans.len()).rev().collect(),
+            DuplicateFields::Outermost => (0..spans.len()).collect(),
+        };
+        let mut seen = HashSet::new();
+        for idx in order {
+            let mut fields: Vec<_> = spans[idx]
+                .iter()
+                .rev()
+                .filter(|field| key(field).map_or(true, |key| seen.insert(key)))
+                .copied()
+                .collect();
+            fields.reverse();
+            spans[idx] = fields;
+        }
+    }
+}
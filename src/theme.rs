@@ -0,0 +1,666 @@
+#[cfg(feature = "ansi")]
+use crate::nu_ansi_term::Color;
+use crate::nu_ansi_term::Style;
+use std::{error::Error, fmt, str::FromStr};
+use tracing::Level;
+
+/// The styles used for each element of a line when ANSI escape codes are enabled.
+///
+/// [`Theme::default`] matches the colors `tracing-glog` has always used. [`Theme::dark`]
+/// and [`Theme::light`] are tuned for terminals with dark and light backgrounds,
+/// respectively, and also color field values by their type.
+///
+/// [`Glog`] styles the prefix and the span context, while [`GlogFields`] styles field
+/// names and messages, so the same theme should usually be passed to both:
+///
+/// ```
+/// use tracing_glog::{Glog, GlogFields, Theme};
+///
+/// let theme = Theme::light();
+/// tracing_subscriber::fmt()
+///     .event_format(Glog::default().with_theme(theme.clone()))
+///     .fmt_fields(GlogFields::default().with_theme(theme))
+///     .init();
+/// ```
+///
+/// # Loading a theme from configuration
+///
+/// Themes can be parsed from strings, which makes it possible to load them from
+/// configuration files or environment variables. A theme is a list of
+/// `element = style` entries separated by `;` or newlines, which are applied in order
+/// on top of [`Theme::default`]. The special `preset` element replaces the entire
+/// theme with `default`, `dark`, `light` or `plain`.
+///
+/// ```
+/// use tracing_glog::Theme;
+///
+/// let theme: Theme = "preset = dark; level.warn = bold 208; field.key = italic #8fbcbb"
+///     .parse()
+///     .expect("invalid theme");
+/// ```
+///
+/// The elements are `level.trace`, `level.debug`, `level.info`, `level.warn`,
+/// `level.error`, `timestamp`, `pid`, `thread`, `target`, `location`, `span.name`,
+/// `span.fields`, `field.key`, `message`, `value.number`, `value.string` and
+/// `value.bool`.
+///
+/// A style is a space-separated list of attributes (`bold`, `dimmed`, `italic`,
+/// `underline`, `blink`, `reverse`, `hidden` and `strikethrough`) and colors. A color
+/// is either a name (`black`, `red`, `green`, `yellow`, `blue`, `purple`, `magenta`,
+/// `cyan`, `white` and `dark-gray`, as well as `light-` variants such as `light-red`), a
+/// number from the 256-color palette, or a truecolor `#rrggbb` value. The first color
+/// is used for the foreground; a color following `on` is used for the background. A
+/// style of `plain` removes all styling.
+///
+/// [`Glog`]: crate::Glog
+/// [`GlogFields`]: crate::GlogFields
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub(crate) trace: Style,
+    pub(crate) debug: Style,
+    pub(crate) info: Style,
+    pub(crate) warn: Style,
+    pub(crate) error: Style,
+    pub(crate) timestamp: Style,
+    pub(crate) pid: Style,
+    pub(crate) thread_name: Style,
+    pub(crate) target: Style,
+    pub(crate) location: Style,
+    pub(crate) span_name: Style,
+    pub(crate) span_fields: Style,
+    pub(crate) field_key: Style,
+    pub(crate) message: Style,
+    pub(crate) number: Style,
+    pub(crate) string: Style,
+    pub(crate) boolean: Style,
+    pub(crate) hash_colors: HashColors,
+}
+
+/// An element of a line that can be styled by a [`Theme`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemeElement {
+    /// The severity of events at the given level.
+    Level(Level),
+    /// The timestamp.
+    Timestamp,
+    /// The process ID.
+    Pid,
+    /// The thread name and, if enabled, the tokio task.
+    ThreadName,
+    /// The target.
+    Target,
+    /// The source location (`file:line`).
+    Location,
+    /// The names of spans in the span context.
+    SpanName,
+    /// The fields of spans in the span context.
+    SpanFields,
+    /// The names of fields.
+    FieldKey,
+    /// The message of an event.
+    Message,
+    /// The values of number fields, including durations and byte sizes.
+    NumberValue,
+    /// The values of string fields.
+    StringValue,
+    /// The values of boolean fields.
+    BoolValue,
+}
+
+impl Theme {
+    /// A theme that doesn't style anything.
+    pub fn plain() -> Theme {
+        let plain = Style::new();
+        Theme {
+            trace: plain,
+            debug: plain,
+            info: plain,
+            warn: plain,
+            error: plain,
+            timestamp: plain,
+            pid: plain,
+            thread_name: plain,
+            target: plain,
+            location: plain,
+            span_name: plain,
+            span_fields: plain,
+            field_key: plain,
+            message: plain,
+            number: plain,
+            string: plain,
+            boolean: plain,
+            hash_colors: HashColors::default(),
+        }
+    }
+
+    /// A theme for terminals with a dark background.
+    pub fn dark() -> Theme {
+        #[cfg(feature = "ansi")]
+        return Theme {
+            trace: Color::LightPurple.normal(),
+            debug: Color::LightBlue.normal(),
+            info: Color::LightGreen.normal(),
+            warn: Color::LightYellow.bold(),
+            error: Color::LightRed.bold(),
+            timestamp: Color::Fixed(245).normal(),
+            pid: Color::Fixed(245).normal(),
+            thread_name: Color::LightCyan.normal(),
+            target: Color::Fixed(250).normal(),
+            location: Style::new().bold(),
+            span_name: Color::LightBlue.bold(),
+            span_fields: Style::new().italic(),
+            field_key: Color::Fixed(250).bold(),
+            message: Style::new(),
+            number: Color::LightCyan.normal(),
+            string: Color::Fixed(180).normal(),
+            boolean: Color::LightPurple.normal(),
+            hash_colors: HashColors::default(),
+        };
+        #[cfg(not(feature = "ansi"))]
+        Theme::plain()
+    }
+
+    /// A theme for terminals with a light background.
+    pub fn light() -> Theme {
+        #[cfg(feature = "ansi")]
+        return Theme {
+            trace: Color::Purple.normal(),
+            debug: Color::Blue.normal(),
+            info: Color::Green.normal(),
+            warn: Color::Fixed(130).bold(),
+            error: Color::Red.bold(),
+            timestamp: Color::Fixed(242).normal(),
+            pid: Color::Fixed(242).normal(),
+            thread_name: Color::Cyan.normal(),
+            target: Color::Fixed(238).normal(),
+            location: Style::new().bold(),
+            span_name: Color::Blue.bold(),
+            span_fields: Style::new().italic(),
+            field_key: Color::Fixed(238).bold(),
+            message: Style::new(),
+            number: Color::Cyan.normal(),
+            string: Color::Fixed(94).normal(),
+            boolean: Color::Purple.normal(),
+            hash_colors: HashColors::default(),
+        };
+        #[cfg(not(feature = "ansi"))]
+        Theme::plain()
+    }
+
+    /// Sets the style of a single element.
+    #[cfg(feature = "ansi")]
+    pub fn with_style(mut self, element: ThemeElement, style: Style) -> Theme {
+        *self.style_mut(element) = style;
+        self
+    }
+
+    /// Returns the style of a single element.
+    #[cfg(feature = "ansi")]
+    pub fn style(&self, element: ThemeElement) -> Style {
+        match element {
+            ThemeElement::Level(level) => self.level(level),
+            ThemeElement::Timestamp => self.timestamp,
+            ThemeElement::Pid => self.pid,
+            ThemeElement::ThreadName => self.thread_name,
+            ThemeElement::Target => self.target,
+            ThemeElement::Location => self.location,
+            ThemeElement::SpanName => self.span_name,
+            ThemeElement::SpanFields => self.span_fields,
+            ThemeElement::FieldKey => self.field_key,
+            ThemeElement::Message => self.message,
+            ThemeElement::NumberValue => self.number,
+            ThemeElement::StringValue => self.string,
+            ThemeElement::BoolValue => self.boolean,
+        }
+    }
+
+    /// Colors the values selected by `hash_colors` with colors derived from their
+    /// hashes, see [`HashColors`]. Hash colors don't apply to lines colored entirely by
+    /// [`Glog::with_line_coloring`].
+    ///
+    /// [`Glog::with_line_coloring`]: crate::Glog::with_line_coloring
+    pub fn with_hash_colors(mut self, hash_colors: HashColors) -> Theme {
+        self.hash_colors = hash_colors;
+        self
+    }
+
+    pub(crate) fn thread_name_style(&self, name: &str) -> Style {
+        match self.hash_colors.thread_names {
+            true => hashed(name.as_bytes(), self.thread_name),
+            false => self.thread_name,
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn task_style(&self, id: &tokio::task::Id) -> Style {
+        match self.hash_colors.tokio_tasks {
+            true => hashed(id.to_string().as_bytes(), self.thread_name),
+            false => self.thread_name,
+        }
+    }
+
+    pub(crate) fn span_name_style(&self, name: &str) -> Style {
+        let hashed_name = match &self.hash_colors.span_names {
+            SpanNames::None => false,
+            SpanNames::All => true,
+            SpanNames::Only(names) => names.iter().any(|n| n == name),
+        };
+        match hashed_name {
+            true => hashed(name.as_bytes(), self.span_name),
+            false => self.span_name,
+        }
+    }
+
+    pub(crate) fn level(&self, level: Level) -> Style {
+        match level {
+            Level::TRACE => self.trace,
+            Level::DEBUG => self.debug,
+            Level::INFO => self.info,
+            Level::WARN => self.warn,
+            Level::ERROR => self.error,
+        }
+    }
+
+    /// Returns this theme with each of its styles layered on top of `base`, for
+    /// elements printed within a line that is colored entirely in `base`.
+    ///
+    /// Hash colors are dropped, as they would replace the line's color.
+    pub(crate) fn layered_on(&self, base: Style) -> Theme {
+        Theme {
+            trace: layer(self.trace, base),
+            debug: layer(self.debug, base),
+            info: layer(self.info, base),
+            warn: layer(self.warn, base),
+            error: layer(self.error, base),
+            timestamp: layer(self.timestamp, base),
+            pid: layer(self.pid, base),
+            thread_name: layer(self.thread_name, base),
+            target: layer(self.target, base),
+            location: layer(self.location, base),
+            span_name: layer(self.span_name, base),
+            span_fields: layer(self.span_fields, base),
+            field_key: layer(self.field_key, base),
+            message: layer(self.message, base),
+            number: layer(self.number, base),
+            string: layer(self.string, base),
+            boolean: layer(self.boolean, base),
+            hash_colors: HashColors::default(),
+        }
+    }
+
+    fn style_mut(&mut self, element: ThemeElement) -> &mut Style {
+        match element {
+            ThemeElement::Level(Level::TRACE) => &mut self.trace,
+            ThemeElement::Level(Level::DEBUG) => &mut self.debug,
+            ThemeElement::Level(Level::INFO) => &mut self.info,
+            ThemeElement::Level(Level::WARN) => &mut self.warn,
+            ThemeElement::Level(Level::ERROR) => &mut self.error,
+            ThemeElement::Timestamp => &mut self.timestamp,
+            ThemeElement::Pid => &mut self.pid,
+            ThemeElement::ThreadName => &mut self.thread_name,
+            ThemeElement::Target => &mut self.target,
+            ThemeElement::Location => &mut self.location,
+            ThemeElement::SpanName => &mut self.span_name,
+            ThemeElement::SpanFields => &mut self.span_fields,
+            ThemeElement::FieldKey => &mut self.field_key,
+            ThemeElement::Message => &mut self.message,
+            ThemeElement::NumberValue => &mut self.number,
+            ThemeElement::StringValue => &mut self.string,
+            ThemeElement::BoolValue => &mut self.boolean,
+        }
+    }
+}
+
+/// Selects values that are colored with a color derived from a hash of the value, so
+/// that, for example, lines from the same thread or the same request can be picked out
+/// as they interleave with others.
+///
+/// The colors are picked from a palette that is readable on both dark and light
+/// backgrounds, replacing the foreground color of the element's style in the
+/// [`Theme`] while keeping its other attributes. The same value always gets the same
+/// color, across runs and processes. Values aren't colored when ANSI escape codes are
+/// disabled, or on lines colored entirely by [`Glog::with_line_coloring`].
+///
+/// ```
+/// use tracing_glog::{Glog, HashColors, Theme};
+///
+/// let hash_colors = HashColors::default()
+///     .with_thread_names(true)
+///     .with_span_name("request");
+/// let glog = Glog::default().with_theme(Theme::default().with_hash_colors(hash_colors));
+/// ```
+///
+/// [`Glog::with_line_coloring`]: crate::Glog::with_line_coloring
+#[derive(Clone, Debug, Default)]
+pub struct HashColors {
+    thread_names: bool,
+    #[cfg(feature = "tokio")]
+    tokio_tasks: bool,
+    span_names: SpanNames,
+}
+
+#[derive(Clone, Debug, Default)]
+enum SpanNames {
+    #[default]
+    None,
+    All,
+    Only(Vec<String>),
+}
+
+impl HashColors {
+    /// Sets whether or not thread names are colored by their hash. Defaults to false.
+    pub fn with_thread_names(self, thread_names: bool) -> Self {
+        HashColors {
+            thread_names,
+            ..self
+        }
+    }
+
+    /// Sets whether or not tokio task IDs are colored by their hash. Defaults to false.
+    #[cfg(feature = "tokio")]
+    pub fn with_tokio_tasks(self, tokio_tasks: bool) -> Self {
+        HashColors {
+            tokio_tasks,
+            ..self
+        }
+    }
+
+    /// Sets whether or not the names of all spans in the span context are colored by
+    /// their hash. Defaults to false.
+    pub fn with_span_names(self, span_names: bool) -> Self {
+        let span_names = match span_names {
+            true => SpanNames::All,
+            false => SpanNames::None,
+        };
+        HashColors { span_names, ..self }
+    }
+
+    /// Colors spans named `name` by the hash of their name. Can be called repeatedly to
+    /// select several span names.
+    pub fn with_span_name(mut self, name: impl Into<String>) -> Self {
+        match &mut self.span_names {
+            SpanNames::All => {}
+            SpanNames::Only(names) => names.push(name.into()),
+            SpanNames::None => self.span_names = SpanNames::Only(vec![name.into()]),
+        }
+        self
+    }
+}
+
+/// Colors from the 256-color palette that are neither too dark for dark backgrounds
+/// nor too light for light backgrounds.
+#[cfg(feature = "ansi")]
+const HASH_PALETTE: [u8; 20] = [
+    31, 32, 35, 37, 38, 62, 66, 68, 71, 72, 97, 98, 130, 131, 133, 136, 166, 168, 170, 172,
+];
+
+/// Returns `base` with its foreground replaced by the color for `value`.
+///
+/// This uses FNV-1a, rather than `std`'s randomly seeded hasher, so that colors are
+/// stable across processes.
+#[cfg(feature = "ansi")]
+fn hashed(value: &[u8], base: Style) -> Style {
+    let hash = value.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    let color = HASH_PALETTE[(hash % HASH_PALETTE.len() as u64) as usize];
+    Style {
+        foreground: Some(Color::Fixed(color)),
+        ..base
+    }
+}
+
+#[cfg(not(feature = "ansi"))]
+fn hashed(_value: &[u8], base: Style) -> Style {
+    base
+}
+
+/// Layers `style` on top of `base`: `style`'s colors take precedence, and the
+/// attributes of both are combined.
+#[cfg(feature = "ansi")]
+pub(crate) fn layer(style: Style, base: Style) -> Style {
+    Style {
+        foreground: style.foreground.or(base.foreground),
+        background: style.background.or(base.background),
+        is_bold: style.is_bold || base.is_bold,
+        is_dimmed: style.is_dimmed || base.is_dimmed,
+        is_italic: style.is_italic || base.is_italic,
+        is_underline: style.is_underline || base.is_underline,
+        is_blink: style.is_blink || base.is_blink,
+        is_reverse: style.is_reverse || base.is_reverse,
+        is_hidden: style.is_hidden || base.is_hidden,
+        is_strikethrough: style.is_strikethrough || base.is_strikethrough,
+    }
+}
+
+#[cfg(not(feature = "ansi"))]
+pub(crate) fn layer(style: Style, _base: Style) -> Style {
+    style
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        #[cfg(feature = "ansi")]
+        return Theme {
+            trace: Color::Purple.normal(),
+            debug: Color::Blue.normal(),
+            info: Color::Green.normal(),
+            warn: Color::Yellow.normal(),
+            error: Color::Red.normal(),
+            timestamp: Style::new().dimmed(),
+            pid: Style::new(),
+            thread_name: Style::new().bold(),
+            target: Style::new().bold(),
+            location: Style::new().bold(),
+            span_name: Style::new().bold(),
+            span_fields: Style::new().italic(),
+            field_key: Style::new().bold(),
+            message: Style::new(),
+            number: Style::new(),
+            string: Style::new(),
+            boolean: Style::new(),
+            hash_colors: HashColors::default(),
+        };
+        #[cfg(not(feature = "ansi"))]
+        Theme::plain()
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ParseThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut theme = Theme::default();
+        for entry in s.split([';', '\n']) {
+            let entry = entry.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            let (element, style) = entry.split_once('=').ok_or_else(|| {
+                ParseThemeError::new(format!("expected `element = style`, found `{entry}`"))
+            })?;
+            let (element, style) = (element.trim(), style.trim());
+
+            if element == "preset" {
+                theme = match style {
+                    "default" => Theme::default(),
+                    "dark" => Theme::dark(),
+                    "light" => Theme::light(),
+                    "plain" => Theme::plain(),
+                    preset => {
+                        return Err(ParseThemeError::new(format!("unknown preset `{preset}`")))
+                    }
+                };
+                continue;
+            }
+
+            let element = match element {
+                "level.trace" => ThemeElement::Level(Level::TRACE),
+                "level.debug" => ThemeElement::Level(Level::DEBUG),
+                "level.info" => ThemeElement::Level(Level::INFO),
+                "level.warn" => ThemeElement::Level(Level::WARN),
+                "level.error" => ThemeElement::Level(Level::ERROR),
+                "timestamp" => ThemeElement::Timestamp,
+                "pid" => ThemeElement::Pid,
+                "thread" => ThemeElement::ThreadName,
+                "target" => ThemeElement::Target,
+                "location" => ThemeElement::Location,
+                "span.name" => ThemeElement::SpanName,
+                "span.fields" => ThemeElement::SpanFields,
+                "field.key" => ThemeElement::FieldKey,
+                "message" => ThemeElement::Message,
+                "value.number" => ThemeElement::NumberValue,
+                "value.string" => ThemeElement::StringValue,
+                "value.bool" => ThemeElement::BoolValue,
+                element => {
+                    return Err(ParseThemeError::new(format!("unknown element `{element}`")))
+                }
+            };
+            *theme.style_mut(element) = parse_style(style)?;
+        }
+        Ok(theme)
+    }
+}
+
+/// Parses a style such as `bold yellow on 236`.
+pub(crate) fn parse_style(s: &str) -> Result<Style, ParseThemeError> {
+    #[cfg_attr(not(feature = "ansi"), allow(unused_mut))]
+    let mut style = Style::new();
+    let mut words = s.split_whitespace();
+    while let Some(word) = words.next() {
+        #[cfg(feature = "ansi")]
+        {
+            style = match word {
+                "plain" => Style::new(),
+                "bold" => style.bold(),
+                "dimmed" => style.dimmed(),
+                "italic" => style.italic(),
+                "underline" => style.underline(),
+                "blink" => style.blink(),
+                "reverse" => style.reverse(),
+                "hidden" => style.hidden(),
+                "strikethrough" => style.strikethrough(),
+                "on" => {
+                    let color = words.next().ok_or_else(|| {
+                        ParseThemeError::new(format!("expected a color after `on` in `{s}`"))
+                    })?;
+                    style.on(parse_color(color)?)
+                }
+                color => style.fg(parse_color(color)?),
+            };
+        }
+        #[cfg(not(feature = "ansi"))]
+        {
+            // styles are never printed without the `ansi` feature, but still reject invalid ones.
+            match word {
+                "plain" | "bold" | "dimmed" | "italic" | "underline" | "blink" | "reverse"
+                | "hidden" | "strikethrough" => {}
+                "on" => {
+                    let color = words.next().ok_or_else(|| {
+                        ParseThemeError::new(format!("expected a color after `on` in `{s}`"))
+                    })?;
+                    parse_color(color)?
+                }
+                color => parse_color(color)?,
+            }
+        }
+    }
+    Ok(style)
+}
+
+#[cfg(feature = "ansi")]
+fn parse_color(s: &str) -> Result<Color, ParseThemeError> {
+    let color = match s {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "purple" | "magenta" => Color::Purple,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "dark-gray" => Color::DarkGray,
+        "light-red" => Color::LightRed,
+        "light-green" => Color::LightGreen,
+        "light-yellow" => Color::LightYellow,
+        "light-blue" => Color::LightBlue,
+        "light-purple" | "light-magenta" => Color::LightPurple,
+        "light-cyan" => Color::LightCyan,
+        "light-gray" => Color::LightGray,
+        s => match s.strip_prefix('#') {
+            Some(hex) => {
+                let (r, g, b) = parse_hex(hex)
+                    .ok_or_else(|| ParseThemeError::new(format!("invalid color `{s}`")))?;
+                Color::Rgb(r, g, b)
+            }
+            None => Color::Fixed(
+                s.parse()
+                    .map_err(|_| ParseThemeError::new(format!("invalid style `{s}`")))?,
+            ),
+        },
+    };
+    Ok(color)
+}
+
+#[cfg(not(feature = "ansi"))]
+fn parse_color(s: &str) -> Result<(), ParseThemeError> {
+    let valid = matches!(
+        s,
+        "black"
+            | "red"
+            | "green"
+            | "yellow"
+            | "blue"
+            | "purple"
+            | "magenta"
+            | "cyan"
+            | "white"
+            | "dark-gray"
+            | "light-red"
+            | "light-green"
+            | "light-yellow"
+            | "light-blue"
+            | "light-purple"
+            | "light-magenta"
+            | "light-cyan"
+            | "light-gray"
+    ) || match s.strip_prefix('#') {
+        Some(hex) => parse_hex(hex)
+            .map(|_| true)
+            .ok_or_else(|| ParseThemeError::new(format!("invalid color `{s}`")))?,
+        None => s.parse::<u8>().is_ok(),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(ParseThemeError::new(format!("invalid style `{s}`")))
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<(u8, u8, u8)> {
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// An error returned when parsing a [`Theme`] fails.
+#[derive(Debug)]
+pub struct ParseThemeError {
+    message: String,
+}
+
+impl ParseThemeError {
+    fn new(message: String) -> Self {
+        ParseThemeError { message }
+    }
+}
+
+impl fmt::Display for ParseThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid theme: {}", self.message)
+    }
+}
+
+impl Error for ParseThemeError {}
@@ -1,5 +1,3 @@
-#[cfg(feature = "ansi")]
-use crate::nu_ansi_term::Style;
 use std::{fmt, io};
 use time::{format_description::FormatItem, formatting::Formattable, OffsetDateTime};
 use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
@@ -75,16 +73,6 @@ where
 {
     fn format_time(&self, writer: &mut Writer<'_>) -> fmt::Result {
         let now = OffsetDateTime::now_utc();
-
-        #[cfg(feature = "ansi")]
-        if writer.has_ansi_escapes() {
-            let style = Style::new().dimmed();
-            write!(writer, "{}", style.prefix())?;
-            format_datetime(writer, now, &self.format)?;
-            write!(writer, "{}", style.suffix())?;
-            return Ok(());
-        }
-
         format_datetime(writer, now, &self.format)
     }
 }
@@ -142,17 +130,6 @@ where
 {
     fn format_time(&self, writer: &mut Writer<'_>) -> fmt::Result {
         let now = OffsetDateTime::now_local().map_err(|_| fmt::Error)?;
-
-        #[cfg(feature = "ansi")]
-        if writer.has_ansi_escapes() {
-            let style = Style::new().dimmed();
-            write!(writer, "{}", style.prefix())?;
-            format_datetime(writer, now, &self.format)?;
-            // necessary to provide space between the time and the PID
-            write!(writer, "{}", style.suffix())?;
-            return Ok(());
-        }
-
         format_datetime(writer, now, &self.format)
     }
 }
@@ -0,0 +1,294 @@
+use crate::redact::glob_matches;
+use std::{
+    fmt::{self, Write},
+    time::Duration,
+};
+use tracing::field::Field;
+
+/// How [`GlogFields`] prints typed field values, such as numbers and byte slices.
+///
+/// By default, values are printed with their `Debug` implementations, as
+/// `tracing-subscriber` prints them.
+///
+/// ```
+/// use tracing_glog::{BytesFormat, GlogFields, ValueFormat};
+///
+/// let values = ValueFormat::default()
+///     .with_float_precision(3)
+///     .with_bytes(BytesFormat::Hex)
+///     .with_thousands_separator('_')
+///     .with_human_durations(true)
+///     .with_byte_size_field("*_bytes");
+/// let fields = GlogFields::default().with_value_format(values);
+/// ```
+///
+/// [`GlogFields`]: crate::GlogFields
+#[derive(Clone, Debug, Default)]
+pub struct ValueFormat {
+    float_precision: Option<usize>,
+    bytes: BytesFormat,
+    thousands_separator: Option<char>,
+    human_durations: bool,
+    byte_sizes: Vec<String>,
+}
+
+/// How byte slices recorded with [`Visit::record_bytes`] are printed.
+///
+/// Defaults to [`BytesFormat::List`].
+///
+/// [`Visit::record_bytes`]: tracing::field::Visit::record_bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BytesFormat {
+    /// A list of bytes in hexadecimal, such as `[de ad be ef]`.
+    #[default]
+    List,
+    /// Hexadecimal digits, such as `deadbeef`.
+    Hex,
+    /// Standard, padded base64, such as `3q2+7w==`.
+    Base64,
+}
+
+impl ValueFormat {
+    /// Prints floating point numbers with `precision` digits after the decimal point.
+    pub fn with_float_precision(self, precision: usize) -> Self {
+        Self {
+            float_precision: Some(precision),
+            ..self
+        }
+    }
+
+    /// Sets how byte slices are printed.
+    pub fn with_bytes(self, bytes: BytesFormat) -> Self {
+        Self { bytes, ..self }
+    }
+
+    /// Separates groups of thousands in numbers with `separator`, such as `1_000_000`
+    /// with `_`.
+    pub fn with_thousands_separator(self, separator: char) -> Self {
+        Self {
+            thousands_separator: Some(separator),
+            ..self
+        }
+    }
+
+    /// Sets whether or not durations of a minute or more are printed in hours, minutes
+    /// and seconds, such as `1h2m3.5s` instead of `3723.5s`. Defaults to false.
+    ///
+    /// Durations are recorded with their `Debug` implementations, so this applies to
+    /// any value recorded with `Debug` that prints like a [`Duration`]. When enabled,
+    /// durations are styled like numbers.
+    ///
+    /// [`Duration`]: std::time::Duration
+    pub fn with_human_durations(self, human_durations: bool) -> Self {
+        Self {
+            human_durations,
+            ..self
+        }
+    }
+
+    /// Prints integer fields whose name matches `glob` as byte sizes, such as `1.5KiB`.
+    /// In `glob`, `*` matches any number of characters and `?` matches a single
+    /// character.
+    pub fn with_byte_size_field(mut self, glob: impl Into<String>) -> Self {
+        self.byte_sizes.push(glob.into());
+        self
+    }
+
+    /// Returns `value`, printed as a float.
+    pub(crate) fn float(&self, value: f64) -> Grouped {
+        let digits = match self.float_precision {
+            // like `Debug`, switch to scientific notation for very large values.
+            Some(precision) if value.abs() >= 1e16 => format!("{value:.precision$e}"),
+            Some(precision) => format!("{value:.precision$}"),
+            None => format!("{value:?}"),
+        };
+        Grouped {
+            digits,
+            separator: self.thousands_separator,
+        }
+    }
+
+    /// Returns the integer field named `name`, which is negative if `negative` is set.
+    pub(crate) fn integer(&self, name: &str, negative: bool, magnitude: u128) -> Integer {
+        let sign = if negative { "-" } else { "" };
+        if self.byte_sizes.iter().any(|glob| glob_matches(glob, name)) {
+            return Integer::ByteSize(sign, magnitude);
+        }
+        Integer::Grouped(Grouped {
+            digits: format!("{sign}{magnitude}"),
+            separator: self.thousands_separator,
+        })
+    }
+
+    /// Returns `value` in the selected [`BytesFormat`].
+    pub(crate) fn bytes<'a>(&self, value: &'a [u8]) -> Bytes<'a> {
+        Bytes(value, self.bytes)
+    }
+
+    /// Returns `value` as a duration, if durations are human-readable and `value` is the
+    /// `Debug` output of a duration in a field other than the message.
+    pub(crate) fn duration(&self, field: &Field, value: &dyn fmt::Debug) -> Option<HumanDuration> {
+        if !self.human_durations || field.name() == "message" {
+            return None;
+        }
+        parse_duration(&format!("{value:?}")).map(HumanDuration)
+    }
+}
+
+/// A number, with its groups of thousands separated if there's a separator.
+pub(crate) struct Grouped {
+    digits: String,
+    separator: Option<char>,
+}
+
+impl fmt::Display for Grouped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = match self.separator {
+            Some(separator) => separator,
+            None => return f.write_str(&self.digits),
+        };
+        let digits = self.digits.trim_start_matches('-');
+        let sign = &self.digits[..self.digits.len() - digits.len()];
+        let int_len = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        // leave values such as `NaN` and `1e100` alone.
+        if int_len == 0
+            || !digits[int_len..]
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b == b'.')
+        {
+            return f.write_str(&self.digits);
+        }
+
+        f.write_str(sign)?;
+        for (idx, digit) in digits[..int_len].chars().enumerate() {
+            if idx > 0 && (int_len - idx) % 3 == 0 {
+                f.write_char(separator)?;
+            }
+            f.write_char(digit)?;
+        }
+        f.write_str(&digits[int_len..])
+    }
+}
+
+pub(crate) enum Integer {
+    Grouped(Grouped),
+    ByteSize(&'static str, u128),
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 8] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
+
+        let (sign, bytes) = match self {
+            Integer::Grouped(grouped) => return grouped.fmt(f),
+            Integer::ByteSize(sign, bytes) => (sign, *bytes),
+        };
+        if bytes < 1024 {
+            return write!(f, "{sign}{bytes}B");
+        }
+        let mut size = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        let size = format!("{size:.1}");
+        let size = size.strip_suffix(".0").unwrap_or(&size);
+        write!(f, "{sign}{size}{}", UNITS[unit])
+    }
+}
+
+pub(crate) struct Bytes<'a>(&'a [u8], BytesFormat);
+
+impl<'a> fmt::Display for Bytes<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BASE64: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        match self.1 {
+            BytesFormat::List => {
+                f.write_char('[')?;
+                for (idx, byte) in self.0.iter().enumerate() {
+                    let separator = if idx == 0 { "" } else { " " };
+                    write!(f, "{separator}{byte:02x}")?;
+                }
+                f.write_char(']')
+            }
+            BytesFormat::Hex => self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}")),
+            BytesFormat::Base64 => {
+                for chunk in self.0.chunks(3) {
+                    let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+                        group | u32::from(byte) << (16 - 8 * i)
+                    });
+                    for i in 0..4 {
+                        match i <= chunk.len() {
+                            true => {
+                                let idx = (group >> (18 - 6 * i)) & 0x3f;
+                                f.write_char(char::from(BASE64[idx as usize]))?
+                            }
+                            false => f.write_char('=')?,
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Parses the `Debug` output of a `Duration`, such as `1.5s` or `250ms`, into
+/// nanoseconds.
+fn parse_duration(value: &str) -> Option<u128> {
+    let (number, unit) = [
+        ("ns", 1),
+        ("µs", 1_000),
+        ("ms", 1_000_000),
+        ("s", NANOS_PER_SEC),
+    ]
+    .into_iter()
+    .find_map(|(suffix, unit)| Some((value.strip_suffix(suffix)?, unit)))?;
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() || !is_digits(int) || !is_digits(frac) || frac.len() > 9 {
+        return None;
+    }
+    let mut nanos = int.parse::<u128>().ok()?.checked_mul(unit)?;
+    let mut scale = unit;
+    for digit in frac.bytes() {
+        scale /= 10;
+        nanos += u128::from(digit - b'0') * scale;
+    }
+    Some(nanos)
+}
+
+/// A duration of a minute or more printed in days, hours, minutes and seconds, such
+/// as `1h2m3.5s`. Shorter durations are printed as `Duration` prints them.
+pub(crate) struct HumanDuration(u128);
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0 / NANOS_PER_SEC;
+        if secs < 60 {
+            let nanos = (self.0 % NANOS_PER_SEC) as u32;
+            return write!(f, "{:?}", Duration::new(secs as u64, nanos));
+        }
+        let (days, hours, mins) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60);
+        if days > 0 {
+            write!(f, "{days}d")?;
+        }
+        if days > 0 || hours > 0 {
+            write!(f, "{hours}h")?;
+        }
+        write!(f, "{mins}m{}", secs % 60)?;
+        let frac = self.0 % NANOS_PER_SEC;
+        if frac > 0 {
+            let frac = format!("{frac:09}");
+            write!(f, ".{}", frac.trim_end_matches('0'))?;
+        }
+        f.write_char('s')
+    }
+}
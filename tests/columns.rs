@@ -0,0 +1,127 @@
+use std::thread;
+use tracing::info;
+use tracing_glog::{ColumnWidth, Glog, GlogFields};
+
+mod common;
+
+/// Returns the lines printed by `f`, in a thread called `thread_name`, from the space
+/// following the process ID on.
+fn capture(glog: Glog, thread_name: &str, f: impl FnOnce() + Send + 'static) -> Vec<String> {
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(glog.with_thread_names(true).with_target(true))
+        .fmt_fields(GlogFields::default())
+        .finish();
+    let dispatch = tracing::Dispatch::new(subscriber);
+    thread::Builder::new()
+        .name(thread_name.to_string())
+        .spawn(move || tracing::dispatcher::with_default(&dispatch, f))
+        .unwrap()
+        .join()
+        .unwrap();
+    let pid = format!(" {}", std::process::id());
+    writer
+        .output()
+        .lines()
+        .map(|line| line.split_once(&pid).unwrap().1.to_string())
+        .collect()
+}
+
+#[test]
+fn fixed_zero_hides_columns() {
+    let glog = Glog::default()
+        .with_thread_name_width(ColumnWidth::Fixed(0))
+        .with_target_width(ColumnWidth::Fixed(0))
+        .with_location_width(ColumnWidth::Fixed(0));
+    let lines = capture(glog, "worker", || info!(target: "app", "m"));
+    assert_eq!(lines, ["] m"]);
+
+    let glog = Glog::default().with_target_width(ColumnWidth::Fixed(0));
+    let lines = capture(glog, "worker", || info!(target: "app", "m"));
+    let line = line!() - 1;
+    assert_eq!(lines, [format!(" worker tests/columns.rs:{line}] m")]);
+}
+
+#[test]
+fn fixed_truncates_and_pads() {
+    let glog = Glog::default()
+        .with_thread_name_width(ColumnWidth::Fixed(4))
+        .with_target_width(ColumnWidth::Fixed(8))
+        .with_location_width(ColumnWidth::Fixed(9));
+    let lines = capture(glog, "worker", || {
+        info!(target: "my_app::db", "m");
+        info!(target: "a", "m");
+    });
+    let line = line!() - 3;
+    assert_eq!(
+        lines,
+        [
+            format!(" work app::db] mns.rs:{line}] m"),
+            format!(" work [a]      mns.rs:{}] m", line + 1),
+        ]
+    );
+
+    let glog = Glog::default().with_thread_name_width(ColumnWidth::Fixed(8));
+    let lines = capture(glog, "w", || info!(target: "app", "m"));
+    let line = line!() - 1;
+    assert_eq!(
+        lines,
+        [format!(" w        [app] tests/columns.rs:{line}] m")]
+    );
+}
+
+#[test]
+fn min_pads_without_truncating() {
+    let glog = Glog::default().with_target_width(ColumnWidth::Min(6));
+    let lines = capture(glog, "w", || {
+        info!(target: "a", "m");
+        info!(target: "app::db", "m");
+    });
+    let line = line!() - 3;
+    assert_eq!(
+        lines,
+        [
+            format!(" w [a]    tests/columns.rs:{line}] m"),
+            format!(" w [app::db] tests/columns.rs:{}] m", line + 1),
+        ]
+    );
+}
+
+#[test]
+fn adaptive_widens() {
+    let glog = Glog::default()
+        .with_target_width(ColumnWidth::Adaptive)
+        .with_location_width(ColumnWidth::Fixed(0));
+    let lines = capture(glog, "w", || {
+        info!(target: "ab", "m");
+        info!(target: "abcd", "m");
+        info!(target: "a", "m");
+    });
+    assert_eq!(lines, [" w [ab]] m", " w [abcd]] m", " w [a]   ] m"]);
+}
+
+#[cfg(feature = "ansi")]
+#[test]
+fn fixed_zero_in_layout() {
+    let layout = "{level} {thread} {target} {location}] {message}"
+        .parse()
+        .unwrap();
+    let glog = Glog::default()
+        .with_layout(layout)
+        .with_theme(tracing_glog::Theme::plain())
+        .with_thread_name_width(ColumnWidth::Fixed(0))
+        .with_target_width(ColumnWidth::Fixed(0))
+        .with_location_width(ColumnWidth::Fixed(0))
+        .with_hyperlinks(tracing_glog::Hyperlinks::file());
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(true)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default())
+        .finish();
+    tracing::subscriber::with_default(subscriber, || info!("m"));
+    assert_eq!(writer.output(), "I ] m\n");
+}
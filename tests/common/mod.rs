@@ -0,0 +1,69 @@
+#![allow(dead_code)]
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use tracing::Dispatch;
+use tracing_glog::{Glog, GlogFields};
+use tracing_subscriber::fmt::MakeWriter;
+
+#[derive(Clone, Default)]
+pub struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+impl TestWriter {
+    /// Returns everything that was written.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+
+    /// Returns everything after the glog prefix (`...file:line] `) of each line.
+    pub fn lines(&self) -> Vec<String> {
+        let buf = self.0.lock().unwrap();
+        String::from_utf8_lossy(&buf)
+            .lines()
+            .map(|line| line.split_once("] ").unwrap().1.to_owned())
+            .collect()
+    }
+}
+
+impl io::Write for TestWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for TestWriter {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Returns the lines printed by `f`, with ANSI escape codes disabled.
+pub fn capture(glog: Glog, fields: GlogFields, f: impl FnOnce()) -> Vec<String> {
+    capture_with_ansi(false, glog, fields, f)
+}
+
+/// Returns the lines printed by `f`, with ANSI escape codes enabled or not.
+pub fn capture_with_ansi(
+    ansi: bool,
+    glog: Glog,
+    fields: GlogFields,
+    f: impl FnOnce(),
+) -> Vec<String> {
+    let writer = TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(ansi)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(fields)
+        .finish();
+    tracing::dispatcher::with_default(&Dispatch::new(subscriber), f);
+    writer.lines()
+}
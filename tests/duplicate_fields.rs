@@ -0,0 +1,102 @@
+use tracing::{field, info, info_span};
+use tracing_glog::{DuplicateFields, Glog, GlogFields};
+
+mod common;
+
+fn capture(duplicate_fields: DuplicateFields, fields: GlogFields, f: impl FnOnce()) -> Vec<String> {
+    let glog = Glog::default().with_duplicate_span_fields(duplicate_fields);
+    common::capture(glog, fields, f)
+}
+
+fn nested() {
+    let outer = info_span!("outer", id = 1, a = "x");
+    let middle = info_span!(parent: &outer, "middle", b = 2);
+    let inner = info_span!(parent: &middle, "inner", id = 3);
+    inner.in_scope(|| info!(id = 4, "hi"));
+}
+
+#[test]
+fn keep() {
+    let lines = capture(DuplicateFields::Keep, GlogFields::default(), nested);
+    assert_eq!(
+        lines,
+        [r#"[outer{id: 1, a: "x"}, middle{b: 2}, inner{id: 3}] hi, id: 4"#]
+    );
+}
+
+#[test]
+fn innermost() {
+    let lines = capture(DuplicateFields::Innermost, GlogFields::default(), nested);
+    assert_eq!(
+        lines,
+        [r#"[outer{a: "x"}, middle{b: 2}, inner{id: 3}] hi, id: 4"#]
+    );
+}
+
+#[test]
+fn outermost() {
+    let lines = capture(DuplicateFields::Outermost, GlogFields::default(), nested);
+    assert_eq!(
+        lines,
+        [r#"[outer{id: 1, a: "x"}, middle{b: 2}, inner] hi, id: 4"#]
+    );
+}
+
+#[test]
+fn compact() {
+    let lines = capture(
+        DuplicateFields::Innermost,
+        GlogFields::default().compact(),
+        nested,
+    );
+    assert_eq!(
+        lines,
+        [r#"[outer{a:x}, middle{b:2}, inner{id:3}] hi, id:4"#]
+    );
+    let lines = capture(
+        DuplicateFields::Outermost,
+        GlogFields::default().compact(),
+        nested,
+    );
+    assert_eq!(
+        lines,
+        [r#"[outer{id:1, a:x}, middle{b:2}, inner] hi, id:4"#]
+    );
+}
+
+#[test]
+fn without_span_names() {
+    let glog = Glog::default()
+        .with_duplicate_span_fields(DuplicateFields::Outermost)
+        .with_span_names(false);
+    let lines = common::capture(glog, GlogFields::default(), nested);
+    assert_eq!(lines, [r#"[id: 1, a: "x", b: 2] hi, id: 4"#]);
+}
+
+#[test]
+fn recorded_fields() {
+    let run = || {
+        let outer = info_span!("outer", id = 1);
+        let inner = info_span!(parent: &outer, "inner", id = field::Empty, b = 2);
+        inner.in_scope(|| info!("before"));
+        inner.record("id", 3);
+        outer.record("id", 5);
+        inner.in_scope(|| info!("after"));
+    };
+    let lines = capture(DuplicateFields::Innermost, GlogFields::default(), run);
+    assert_eq!(
+        lines,
+        [
+            "[outer{id: 1}, inner{b: 2}] before",
+            "[outer, inner{b: 2, id: 3}] after"
+        ]
+    );
+    let lines = capture(DuplicateFields::Outermost, GlogFields::default(), run);
+    assert_eq!(
+        lines,
+        [
+            "[outer{id: 1}, inner{b: 2}] before",
+            "[outer{id: 5}, inner{b: 2}] after"
+        ]
+    );
+}
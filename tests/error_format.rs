@@ -0,0 +1,201 @@
+#![cfg_attr(tracing_glog_unstable, feature(error_generic_member_access))]
+
+use std::{error::Error, fmt};
+use tracing::info;
+use tracing_glog::{ErrorFormat, Glog, GlogFields, Multiline};
+
+mod common;
+
+#[derive(Debug)]
+struct TestError(&'static str, Option<Box<TestError>>);
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Error for TestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1
+            .as_deref()
+            .map(|error| error as &(dyn Error + 'static))
+    }
+}
+
+fn capture(format: ErrorFormat, logfmt: bool) -> Vec<String> {
+    let glog = Glog::default().with_multiline(Multiline::RepeatPrefix);
+    let fields = GlogFields::default()
+        .use_logfmt(logfmt)
+        .with_error_format(format);
+    common::capture(glog, fields, || {
+        let inner = TestError("inner cause", None);
+        let error = TestError("outer failure", Some(Box::new(inner)));
+        info!(error = &error as &(dyn Error + 'static), "failed");
+        let error = TestError("alone", None);
+        info!(error = &error as &(dyn Error + 'static), "failed");
+    })
+}
+
+#[test]
+fn top_level() {
+    assert_eq!(
+        capture(ErrorFormat::TopLevel, false),
+        ["failed, error: outer failure", "failed, error: alone"]
+    );
+    assert_eq!(
+        capture(ErrorFormat::TopLevel, true),
+        [
+            r#"msg=failed error="outer failure""#,
+            "msg=failed error=alone"
+        ]
+    );
+}
+
+#[test]
+fn sources() {
+    assert_eq!(
+        capture(ErrorFormat::Sources, false),
+        [
+            "failed, error: outer failure, error.sources: [inner cause]",
+            "failed, error: alone",
+        ]
+    );
+    assert_eq!(
+        capture(ErrorFormat::Sources, true),
+        [
+            r#"msg=failed error="outer failure" error.sources="[inner cause]""#,
+            "msg=failed error=alone",
+        ]
+    );
+}
+
+#[test]
+fn chain() {
+    let chain = ErrorFormat::Chain(" <- ".into());
+    assert_eq!(
+        capture(chain.clone(), false),
+        [
+            "failed, error: outer failure <- inner cause",
+            "failed, error: alone"
+        ]
+    );
+    assert_eq!(
+        capture(chain, true),
+        [
+            r#"msg=failed error="outer failure <- inner cause""#,
+            "msg=failed error=alone",
+        ]
+    );
+}
+
+#[test]
+fn alternate() {
+    assert_eq!(
+        capture(ErrorFormat::Alternate, false),
+        [
+            "failed, error: outer failure: inner cause",
+            "failed, error: alone"
+        ]
+    );
+    assert_eq!(
+        capture(ErrorFormat::Alternate, true),
+        [
+            r#"msg=failed error="outer failure: inner cause""#,
+            "msg=failed error=alone",
+        ]
+    );
+}
+
+#[test]
+fn debug() {
+    assert_eq!(
+        capture(ErrorFormat::Debug, false),
+        [
+            r#"failed, error: TestError("outer failure", Some(TestError("inner cause", None)))"#,
+            r#"failed, error: TestError("alone", None)"#,
+        ]
+    );
+    assert_eq!(
+        capture(ErrorFormat::Debug, true),
+        [
+            r#"msg=failed error="TestError(\"outer failure\", Some(TestError(\"inner cause\", None)))""#,
+            r#"msg=failed error="TestError(\"alone\", None)""#,
+        ]
+    );
+}
+
+#[test]
+fn caused_by() {
+    assert_eq!(
+        capture(ErrorFormat::CausedBy, false),
+        [
+            "failed, error: outer failure",
+            "[...] Caused by:",
+            "[...]     0: inner cause",
+            "failed, error: alone",
+        ]
+    );
+    // logfmt values stay on a single line.
+    assert_eq!(
+        capture(ErrorFormat::CausedBy, true),
+        [
+            r#"msg=failed error="outer failure\nCaused by:\n    0: inner cause""#,
+            "msg=failed error=alone",
+        ]
+    );
+}
+
+#[cfg(tracing_glog_unstable)]
+mod backtraces {
+    use super::*;
+    use std::{backtrace::Backtrace, error::Request};
+
+    #[derive(Debug)]
+    struct TracedError(Backtrace);
+
+    impl fmt::Display for TracedError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("traced")
+        }
+    }
+
+    impl Error for TracedError {
+        fn provide<'a>(&'a self, request: &mut Request<'a>) {
+            request.provide_ref::<Backtrace>(&self.0);
+        }
+    }
+
+    fn capture(backtraces: bool, logfmt: bool) -> Vec<String> {
+        let glog = Glog::default().with_multiline(Multiline::RepeatPrefix);
+        let fields = GlogFields::default()
+            .use_logfmt(logfmt)
+            .with_error_backtraces(backtraces);
+        common::capture(glog, fields, || {
+            let error = TracedError(Backtrace::force_capture());
+            info!(error = &error as &(dyn Error + 'static), "failed");
+        })
+    }
+
+    #[test]
+    fn disabled() {
+        assert_eq!(capture(false, false), ["failed, error: traced"]);
+    }
+
+    #[test]
+    fn multiline() {
+        let lines = capture(true, false);
+        assert_eq!(
+            lines[..2],
+            ["failed, error: traced", "[...] Stack backtrace:"]
+        );
+        assert!(lines.len() > 2);
+    }
+
+    #[test]
+    fn logfmt() {
+        let lines = capture(true, true);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with(r#"msg=failed error="traced\nStack backtrace:\n"#));
+    }
+}
@@ -0,0 +1,45 @@
+use tracing::{field::Empty, info, info_span};
+use tracing_glog::{Glog, GlogFields};
+
+mod common;
+
+fn capture(fields: GlogFields) -> Vec<String> {
+    common::capture(Glog::default(), fields, || {
+        info!(
+            tab = "a\tb",
+            esc = "a\x1b[31mb",
+            quote = "say \"hi\"",
+            "m\tx"
+        );
+        info!(t = "c[d\te", q = "ab\"");
+        let span = info_span!("s", t = "c[d\te", c = Empty);
+        span.record("c", 1);
+        span.record("c", 2);
+        span.in_scope(|| info!("in"));
+    })
+}
+
+#[test]
+fn quoted() {
+    assert_eq!(
+        capture(GlogFields::default()),
+        [
+            r#"m\tx, tab: "a\tb", esc: "a\u{1b}[31mb", quote: "say \"hi\"""#,
+            r#"t: "c[d\te", q: "ab\"""#,
+            r#"[s{t: "c[d\te", c: 2}] in"#,
+        ]
+    );
+}
+
+#[test]
+fn unquoted() {
+    assert_eq!(
+        capture(GlogFields::default().compact()),
+        [
+            // the escaped `ESC[` would leave a bracket open, so the value is quoted.
+            r#"m\tx, tab:a\tb, esc:"a\u{1b}[31mb", quote:say "hi""#,
+            r#"t:"c[d\te", q:"ab\"""#,
+            r#"[s{t:"c[d\te", c:2}] in"#,
+        ]
+    );
+}
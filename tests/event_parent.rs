@@ -0,0 +1,86 @@
+use common::TestWriter;
+use std::thread;
+use tracing::{info, info_span, Dispatch};
+use tracing_glog::{Glog, GlogFields};
+
+mod common;
+
+fn dispatch(writer: &TestWriter) -> Dispatch {
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(Glog::default())
+        .fmt_fields(GlogFields::default())
+        .finish();
+    Dispatch::new(subscriber)
+}
+
+#[test]
+fn explicit_parent_overrides_current_span() {
+    let writer = TestWriter::default();
+    tracing::dispatcher::with_default(&dispatch(&writer), || {
+        let request = info_span!("request", id = 1);
+        let other = info_span!("other");
+        let _guard = other.enter();
+        info!(parent: &request, "handled");
+    });
+
+    assert_eq!(writer.lines(), ["[request{id: 1}] handled"]);
+}
+
+#[test]
+fn explicit_root_has_no_span_context() {
+    let writer = TestWriter::default();
+    tracing::dispatcher::with_default(&dispatch(&writer), || {
+        let span = info_span!("request", id = 1);
+        let _guard = span.enter();
+        info!(parent: None, "detached");
+        info!("contextual");
+    });
+
+    assert_eq!(writer.lines(), ["detached", "[request{id: 1}] contextual"]);
+}
+
+#[test]
+fn background_task_events() {
+    let writer = TestWriter::default();
+    let dispatch = dispatch(&writer);
+    tracing::dispatcher::with_default(&dispatch, || {
+        let span = info_span!("request", id = 1);
+        let _guard = span.enter();
+
+        let background = {
+            let dispatch = dispatch.clone();
+            let span = span.clone();
+            thread::spawn(move || {
+                tracing::dispatcher::with_default(&dispatch, || {
+                    // the foreground span is not entered on this thread.
+                    info!("detached");
+                    info!(parent: &span, "on behalf of request");
+                })
+            })
+        };
+        background.join().unwrap();
+    });
+
+    assert_eq!(
+        writer.lines(),
+        ["detached", "[request{id: 1}] on behalf of request"]
+    );
+}
+
+#[tokio::test]
+async fn spawned_task_without_instrument() {
+    let writer = TestWriter::default();
+    let _default = tracing::dispatcher::set_default(&dispatch(&writer));
+
+    let span = info_span!("request", id = 1);
+    let handle = {
+        let _guard = span.enter();
+        tokio::spawn(async { info!("background") })
+    };
+    handle.await.unwrap();
+    info!(parent: &span, "done");
+
+    assert_eq!(writer.lines(), ["background", "[request{id: 1}] done"]);
+}
@@ -0,0 +1,133 @@
+use tracing::{
+    callsite::{Callsite, Identifier},
+    field::{FieldSet, Value},
+    metadata::Kind,
+    subscriber::Interest,
+    Event, Level, Metadata,
+};
+use tracing_glog::{FilePath, Glog, GlogFields};
+
+mod common;
+
+struct TestCallsite;
+
+impl Callsite for TestCallsite {
+    fn set_interest(&self, _: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        unimplemented!("the callsite is never registered")
+    }
+}
+
+static CALLSITE: TestCallsite = TestCallsite;
+
+/// Returns the source location printed for an event with the given metadata, like
+/// those that `tracing-log` creates for `log` records.
+fn location(
+    file_path: FilePath,
+    file: Option<&'static str>,
+    line: Option<u32>,
+    module_path: Option<&'static str>,
+) -> String {
+    let fields = FieldSet::new(&["message"], Identifier(&CALLSITE));
+    let metadata: &'static Metadata<'static> = Box::leak(Box::new(Metadata::new(
+        "event",
+        "target",
+        Level::INFO,
+        file,
+        line,
+        module_path,
+        fields,
+        Kind::EVENT,
+    )));
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(Glog::default().with_file_path(file_path))
+        .fmt_fields(GlogFields::default())
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        let message = metadata.fields().field("message").unwrap();
+        let values = [(&message, Some(&"m" as &dyn Value))];
+        Event::dispatch(metadata, &metadata.fields().value_set(&values));
+    });
+    let output = writer.output();
+    let (prefix, _) = output.split_once("] ").unwrap();
+    prefix.rsplit(' ').next().unwrap().to_string()
+}
+
+fn file_location(file_path: FilePath, file: &'static str) -> String {
+    location(file_path, Some(file), Some(42), Some("my_crate::db"))
+}
+
+#[test]
+fn full_and_basename() {
+    let file = "/home/user/project/src/db/mod.rs";
+    assert_eq!(file_location(FilePath::Full, file), format!("{file}:42"));
+    assert_eq!(file_location(FilePath::Basename, file), "mod.rs:42");
+    assert_eq!(
+        file_location(FilePath::Basename, r"C:\project\src\lib.rs"),
+        "lib.rs:42"
+    );
+}
+
+#[test]
+fn strip_prefixes() {
+    let strip =
+        || FilePath::StripPrefixes(vec!["/home/user/project".to_string(), "/home/".to_string()]);
+    let cases = [
+        // the first matching prefix is stripped, along with the separator after it.
+        ("/home/user/project/src/main.rs", "src/main.rs:42"),
+        ("/home/other/src/main.rs", "other/src/main.rs:42"),
+        ("/opt/src/main.rs", "/opt/src/main.rs:42"),
+        ("src/main.rs", "src/main.rs:42"),
+    ];
+    for (file, expected) in cases {
+        assert_eq!(file_location(strip(), file), expected, "{file}");
+    }
+}
+
+#[test]
+fn shorten_registry() {
+    let cases = [
+        (
+            "/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.21.0/src/runtime/mod.rs",
+            "tokio@1.21.0/src/runtime/mod.rs:42",
+        ),
+        (
+            "/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tracing-subscriber-0.3.18/src/fmt/mod.rs",
+            "tracing-subscriber@0.3.18/src/fmt/mod.rs:42",
+        ),
+        (
+            "/home/user/.cargo/registry/src/github.com-1ecc6299db9ec823/h2-0.3.26-alpha.1/src/lib.rs",
+            "h2@0.3.26-alpha.1/src/lib.rs:42",
+        ),
+        (
+            r"C:\Users\user\.cargo\registry\src\index.crates.io-6f17d22bba15001f\log-0.4.20\src\lib.rs",
+            r"log@0.4.20/src\lib.rs:42",
+        ),
+        // paths outside of the registry are printed in full.
+        ("src/main.rs", "src/main.rs:42"),
+        ("/home/user/.cargo/git/checkouts/repo-123/src/lib.rs", "/home/user/.cargo/git/checkouts/repo-123/src/lib.rs:42"),
+    ];
+    for (file, expected) in cases {
+        assert_eq!(
+            file_location(FilePath::ShortenRegistry, file),
+            expected,
+            "{file}"
+        );
+    }
+}
+
+#[test]
+fn events_without_a_file() {
+    let without_file = |line, module_path| location(FilePath::Full, None, line, module_path);
+    assert_eq!(without_file(None, Some("my_crate::db")), "my_crate::db");
+    assert_eq!(without_file(Some(42), Some("my_crate::db")), "my_crate::db");
+    assert_eq!(without_file(None, None), "target");
+
+    // and files without a line.
+    let location = location(FilePath::Basename, Some("src/lib.rs"), None, None);
+    assert_eq!(location, "lib.rs");
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "ansi")]
+
+use std::thread;
+use tracing::{info, info_span, warn, Dispatch};
+use tracing_glog::{Glog, GlogFields, HashColors, Theme};
+
+mod common;
+
+/// The colors that hash colors are picked from.
+const PALETTE: [u8; 20] = [
+    31, 32, 35, 37, 38, 62, 66, 68, 71, 72, 97, 98, 130, 131, 133, 136, 166, 168, 170, 172,
+];
+
+fn glog(hash_colors: HashColors) -> Glog {
+    Glog::default()
+        .with_theme(Theme::plain().with_hash_colors(hash_colors))
+        .with_thread_names(true)
+}
+
+fn capture(glog: Glog, f: impl FnOnce()) -> Vec<String> {
+    let fields = GlogFields::default().with_theme(Theme::plain());
+    common::capture_with_ansi(true, glog, fields, f)
+}
+
+/// Returns the full lines printed by `f` in a thread called `thread_name`.
+fn capture_in_thread(glog: Glog, thread_name: &str, f: fn()) -> String {
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(true)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default().with_theme(Theme::plain()))
+        .finish();
+    let dispatch = Dispatch::new(subscriber);
+    thread::Builder::new()
+        .name(thread_name.to_string())
+        .spawn(move || tracing::dispatcher::with_default(&dispatch, f))
+        .unwrap()
+        .join()
+        .unwrap();
+    writer.output()
+}
+
+/// Returns the color of the thread name in `output`, if it has one.
+fn thread_name_color(output: &str, thread_name: &str) -> Option<u8> {
+    let end = output.find(&format!("{thread_name}\x1b[0m"))?;
+    let start = output[..end].rfind("\x1b[38;5;")? + "\x1b[38;5;".len();
+    output[start..end].strip_suffix('m')?.parse().ok()
+}
+
+#[test]
+fn thread_names() {
+    let hash_colors = || HashColors::default().with_thread_names(true);
+    let color = |name: &str| {
+        let output = capture_in_thread(glog(hash_colors()), name, || info!("m"));
+        thread_name_color(&output, name).unwrap_or_else(|| panic!("{output:?}"))
+    };
+
+    // FNV-1a hashes are stable across runs and processes, so these never change.
+    assert_eq!(color("main"), 166);
+    assert_eq!(color("worker-1"), 62);
+    assert_eq!(color("worker-2"), 130);
+    assert_eq!(color("tokio-runtime-worker"), 131);
+    assert_eq!(color("main"), color("main"));
+
+    let mut colors: Vec<_> = (0..200).map(|i| color(&format!("t{i}"))).collect();
+    assert!(
+        colors.iter().all(|color| PALETTE.contains(color)),
+        "{colors:?}"
+    );
+    colors.sort_unstable();
+    colors.dedup();
+    assert_eq!(colors, PALETTE);
+
+    let output = capture_in_thread(glog(HashColors::default()), "main", || info!("m"));
+    assert!(output.contains(" main tests/"), "{output:?}");
+}
+
+#[test]
+fn span_names() {
+    let run = || {
+        info_span!("request").in_scope(|| info_span!("db").in_scope(|| info!("m")));
+    };
+
+    let lines = capture(glog(HashColors::default().with_span_names(true)), run);
+    assert_eq!(
+        lines,
+        ["[\x1b[38;5;170mrequest\x1b[0m, \x1b[38;5;98mdb\x1b[0m] m"]
+    );
+
+    let lines = capture(glog(HashColors::default().with_span_name("db")), run);
+    assert_eq!(lines, ["[request, \x1b[38;5;98mdb\x1b[0m] m"]);
+
+    // the other attributes of the theme's style are kept.
+    let theme = Theme::default().with_hash_colors(HashColors::default().with_span_name("db"));
+    let lines = capture(Glog::default().with_theme(theme), run);
+    assert_eq!(
+        lines,
+        ["[\x1b[1mrequest\x1b[0m, \x1b[1;38;5;98mdb\x1b[0m] m"]
+    );
+}
+
+#[test]
+fn not_on_colored_lines() {
+    let hash_colors = HashColors::default().with_span_names(true);
+    let glog = glog(hash_colors).with_line_coloring(true);
+    let lines = capture(glog, || info_span!("db").in_scope(|| warn!("m")));
+    assert!(
+        lines.iter().all(|line| !line.contains("38;5;")),
+        "{lines:?}"
+    );
+}
@@ -0,0 +1,103 @@
+use tracing::info;
+use tracing_glog::{Glog, GlogFields, Hyperlinks};
+
+mod common;
+
+#[test]
+fn unknown_placeholder() {
+    let error = Hyperlinks::new("file://{abs_path}#{column}").unwrap_err();
+    assert_eq!(error.offset(), 18);
+    assert_eq!(
+        error.to_string(),
+        "invalid URL template at offset 18: unknown placeholder `{column}`, expected one of \
+         `abs_path`, `rel_path`, `path` and `line`"
+    );
+}
+
+#[test]
+fn unbalanced_braces() {
+    let error = Hyperlinks::new("file://{abs_path").unwrap_err();
+    assert_eq!(error.offset(), 7);
+    assert_eq!(
+        error.to_string(),
+        "invalid URL template at offset 7: unclosed `{`"
+    );
+
+    let error = Hyperlinks::new("file://abs_path}").unwrap_err();
+    assert_eq!(error.offset(), 15);
+    assert_eq!(
+        error.to_string(),
+        "invalid URL template at offset 15: unmatched `}`, use `}}` for a literal brace"
+    );
+}
+
+#[cfg(feature = "ansi")]
+fn capture(hyperlinks: Hyperlinks) -> (String, u32) {
+    let glog = Glog::default()
+        .with_hyperlinks(hyperlinks)
+        .with_theme(tracing_glog::Theme::plain());
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(true)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default())
+        .finish();
+    let mut line = 0;
+    tracing::subscriber::with_default(subscriber, || {
+        line = line!() + 1;
+        info!("m");
+    });
+    (writer.output(), line)
+}
+
+#[cfg(feature = "ansi")]
+/// Returns `file:line` as a hyperlink to `url`.
+fn link(url: &str, line: u32) -> String {
+    format!("\x1b]8;;{url}\x1b\\tests/hyperlinks.rs:{line}\x1b]8;;\x1b\\")
+}
+
+#[cfg(feature = "ansi")]
+#[test]
+fn presets() {
+    let (output, line) = capture(Hyperlinks::vscode().with_workspace_root("/ws"));
+    let url = format!("vscode://file/ws/tests/hyperlinks.rs:{line}");
+    assert!(output.contains(&link(&url, line)), "{output:?}");
+
+    let (output, line) = capture(Hyperlinks::file().with_workspace_root("/ws/"));
+    let url = "file:///ws/tests/hyperlinks.rs";
+    assert!(output.contains(&link(url, line)), "{output:?}");
+}
+
+#[cfg(feature = "ansi")]
+#[test]
+fn windows_workspace_root() {
+    let (output, line) = capture(Hyperlinks::vscode().with_workspace_root(r"C:\ws"));
+    let url = format!("vscode://file/C:/ws/tests/hyperlinks.rs:{line}");
+    assert!(output.contains(&link(&url, line)), "{output:?}");
+
+    let (output, line) = capture(Hyperlinks::file().with_workspace_root(r"C:\ws"));
+    let url = "file:///C:/ws/tests/hyperlinks.rs";
+    assert!(output.contains(&link(url, line)), "{output:?}");
+}
+
+#[cfg(feature = "ansi")]
+#[test]
+fn template() {
+    let hyperlinks = Hyperlinks::new("https://host/{{x}}/{rel_path}#L{line} {path} {abs_path}")
+        .unwrap()
+        .with_workspace_root("/my ws/é");
+    let (output, line) = capture(hyperlinks);
+    let url = format!(
+        "https://host/{{x}}/tests/hyperlinks.rs#L{line} tests/hyperlinks.rs \
+         /my%20ws/%C3%A9/tests/hyperlinks.rs"
+    );
+    assert!(output.contains(&link(&url, line)), "{output:?}");
+}
+
+#[test]
+fn no_hyperlinks_without_ansi() {
+    let glog = Glog::default().with_hyperlinks(Hyperlinks::file());
+    let lines = common::capture(glog, GlogFields::default(), || info!("m"));
+    assert_eq!(lines, ["m"]);
+}
@@ -0,0 +1,93 @@
+use std::thread;
+use tracing::{info, info_span, Dispatch};
+use tracing_glog::{Glog, GlogFields, Layout};
+
+mod common;
+
+fn capture(template: &str, f: impl FnOnce()) -> String {
+    let layout = Layout::parse(template).unwrap();
+    let glog = Glog::default().with_layout(layout);
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    writer.output()
+}
+
+#[test]
+fn unknown_placeholder() {
+    let error = Layout::parse("{level} {lvel}").unwrap_err();
+    assert_eq!(error.offset(), 8);
+    assert_eq!(
+        error.to_string(),
+        "invalid layout at offset 8: unknown placeholder `{lvel}`, expected one of `level`, \
+         `time`, `pid`, `tid`, `thread`, `task`, `target`, `file`, `line`, `location`, \
+         `promoted`, `spans`, `message` and `fields`"
+    );
+}
+
+#[test]
+fn unbalanced_braces() {
+    let error = Layout::parse("{level} {message").unwrap_err();
+    assert_eq!(error.offset(), 8);
+    assert_eq!(
+        error.to_string(),
+        "invalid layout at offset 8: unclosed `{`"
+    );
+
+    let error = "{level}] message}".parse::<Layout>().unwrap_err();
+    assert_eq!(error.offset(), 16);
+    assert_eq!(
+        error.to_string(),
+        "invalid layout at offset 16: unmatched `}`, use `}}` for a literal brace"
+    );
+}
+
+#[test]
+fn escaped_braces() {
+    let output = capture("{{{level}}} {{message}} {message}", || info!("hi"));
+    assert_eq!(output, "{I} {message} hi\n");
+}
+
+#[test]
+fn empty_placeholders_leave_no_gaps() {
+    let output = capture("{level} {spans} {message} {fields}", || {
+        info!("outside");
+        info!(a = 1, "with field");
+        info_span!("s").in_scope(|| info!(""));
+    });
+    assert_eq!(output, "I outside\nI with field a: 1\nI [s]\n");
+}
+
+#[test]
+fn tid() {
+    let output = capture("{tid}] {message}", || {
+        info!("main");
+        let dispatch = tracing::dispatcher::get_default(Dispatch::clone);
+        thread::spawn(move || tracing::dispatcher::with_default(&dispatch, || info!("spawned")))
+            .join()
+            .unwrap();
+    });
+    let lines: Vec<_> = output
+        .lines()
+        .map(|line| line.split_once("] ").unwrap())
+        .collect();
+    assert_eq!(lines[0].1, "main");
+    assert_eq!(lines[1].1, "spawned");
+    assert!(lines[0].0.parse::<u64>().is_ok(), "{output:?}");
+    assert!(lines[1].0.parse::<u64>().is_ok(), "{output:?}");
+    assert_ne!(lines[0].0, lines[1].0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn tid_is_kernel_thread_id() {
+    let output = capture("{tid}] {message}", || info!("hi"));
+    let thread_self = std::fs::read_link("/proc/thread-self").unwrap();
+    let tid = thread_self.file_name().unwrap().to_str().unwrap();
+    assert_eq!(output, format!("{tid}] hi\n"));
+}
@@ -0,0 +1,65 @@
+use tracing::info;
+use tracing_glog::{Escaping, Glog, GlogFields, Multiline};
+
+mod common;
+
+fn capture(multiline: Multiline) -> Vec<String> {
+    let glog = Glog::default().with_multiline(multiline);
+    common::capture(glog, GlogFields::default(), || {
+        info!("first\nsecond\r\nthird\x1b[1m");
+        info!(e = %"l1\nl2");
+    })
+}
+
+#[test]
+fn keep_leaves_newlines_to_escaping() {
+    assert_eq!(
+        capture(Multiline::Keep),
+        [r"first\nsecond\r\nthird\u{1b}[1m", r"e: l1\nl2"]
+    );
+}
+
+#[test]
+fn keep_prints_newlines_without_escaping() {
+    let glog = Glog::default().with_multiline(Multiline::Keep);
+    let fields = GlogFields::default().with_escaping(Escaping::Never);
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(fields)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || info!("first\nsecond"));
+    assert!(writer.output().ends_with("] first\nsecond\n"));
+}
+
+#[test]
+fn escape_escapes_newlines() {
+    assert_eq!(
+        capture(Multiline::Escape),
+        [r"first\nsecond\r\nthird\u{1b}[1m", r"e: l1\nl2"]
+    );
+}
+
+#[test]
+fn escape_ignores_escaping() {
+    let glog = Glog::default().with_multiline(Multiline::Escape);
+    let fields = GlogFields::default().with_escaping(Escaping::Never);
+    let lines = common::capture(glog, fields, || info!("first\nsecond\x1b[1m"));
+    assert_eq!(lines, ["first\\nsecond\x1b[1m"]);
+}
+
+#[test]
+fn repeat_prefix_continues_lines() {
+    assert_eq!(
+        capture(Multiline::RepeatPrefix),
+        [
+            "first",
+            "[...] second",
+            r"[...] third\u{1b}[1m",
+            "e: l1",
+            "[...] l2"
+        ]
+    );
+}
@@ -0,0 +1,53 @@
+use tracing::{info, info_span};
+use tracing_glog::{Glog, GlogFields, Layout};
+
+mod common;
+
+fn capture(glog: Glog, fields: GlogFields, rid: &str) -> Vec<String> {
+    let glog = glog.with_promoted_fields(["rid"]);
+    common::capture(glog, fields, || {
+        info_span!("req", rid, n = 1).in_scope(|| info!("done"));
+    })
+}
+
+#[test]
+fn default() {
+    let lines = capture(Glog::default(), GlogFields::default(), "a\"b c");
+    assert_eq!(lines, [r#"rid="a\"b c" [req{n: 1}] done"#]);
+}
+
+#[test]
+fn compact() {
+    let lines = capture(Glog::default(), GlogFields::default().compact(), "ab\"");
+    assert_eq!(lines, [r#"rid="ab\"" [req{n:1}] done"#]);
+    let lines = capture(Glog::default(), GlogFields::default().compact(), "\"ab\"");
+    assert_eq!(lines, [r#"rid="\"ab\"" [req{n:1}] done"#]);
+}
+
+#[test]
+fn logfmt() {
+    let lines = capture(Glog::default(), GlogFields::default().logfmt(), "a\"b");
+    assert_eq!(lines, [r#"rid="a\"b" [req{n=1}] msg=done"#]);
+}
+
+#[test]
+fn escaped() {
+    let lines = capture(Glog::default(), GlogFields::default(), "a\nb");
+    assert_eq!(lines, [r#"rid="a\nb" [req{n: 1}] done"#]);
+}
+
+#[test]
+fn layout_without_placeholder() {
+    let layout = Layout::parse("{level}] {spans} {message}").unwrap();
+    let glog = Glog::default().with_layout(layout);
+    let lines = capture(glog, GlogFields::default(), "x");
+    assert_eq!(lines, [r#"[req{rid: "x", n: 1}] done"#]);
+}
+
+#[test]
+fn layout_with_placeholder() {
+    let layout = Layout::parse("{level}] {promoted} {spans} {message}").unwrap();
+    let glog = Glog::default().with_layout(layout);
+    let lines = capture(glog, GlogFields::default(), "x");
+    assert_eq!(lines, ["rid=x [req{n: 1}] done"]);
+}
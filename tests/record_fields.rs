@@ -0,0 +1,61 @@
+use tracing::{field::Empty, info, info_span};
+use tracing_glog::{Glog, GlogFields};
+
+mod common;
+
+fn record_twice(fields: GlogFields, a: &'static str) -> Vec<String> {
+    common::capture(Glog::default(), fields, || {
+        let span = info_span!("req", a, c = Empty);
+        span.record("c", 1);
+        span.record("c", 2);
+        info!(parent: &span, "done");
+    })
+}
+
+#[test]
+fn recorded_fields_replace_existing_ones() {
+    let lines = record_twice(GlogFields::default(), "x");
+    assert_eq!(lines, [r#"[req{a: "x", c: 2}] done"#]);
+}
+
+#[test]
+fn compact_values_containing_separators() {
+    let lines = record_twice(GlogFields::default().compact(), "x, c:9");
+    assert_eq!(lines, [r#"[req{a:"x, c:9", c:2}] done"#]);
+}
+
+#[test]
+fn compact_values_with_unbalanced_brackets() {
+    let lines = record_twice(GlogFields::default().compact(), "paren (open");
+    assert_eq!(lines, [r#"[req{a:"paren (open", c:2}] done"#]);
+
+    let lines = record_twice(GlogFields::default().compact(), "close)");
+    assert_eq!(lines, [r#"[req{a:"close)", c:2}] done"#]);
+}
+
+#[test]
+fn compact_values_with_quotes() {
+    let lines = record_twice(GlogFields::default().compact(), r#"say "hi", c:9"#);
+    assert_eq!(lines, [r#"[req{a:"say \"hi\", c:9", c:2}] done"#]);
+}
+
+#[test]
+fn logfmt_values() {
+    let lines = record_twice(GlogFields::default().logfmt(), "x c=9");
+    assert_eq!(lines, [r#"[req{a="x c=9" c=2}] msg=done"#]);
+
+    let lines = record_twice(GlogFields::default().logfmt(), "(open");
+    assert_eq!(lines, [r#"[req{a="(open" c=2}] msg=done"#]);
+}
+
+#[test]
+fn recorded_fields_are_appended_in_order() {
+    let lines = common::capture(Glog::default(), GlogFields::default(), || {
+        let span = info_span!("req", a = 1, b = Empty, c = Empty);
+        span.record("c", 3);
+        span.record("b", 2);
+        span.record("a", 0);
+        info!(parent: &span, "done");
+    });
+    assert_eq!(lines, ["[req{a: 0, c: 3, b: 2}] done"]);
+}
@@ -0,0 +1,150 @@
+use std::{error::Error, fmt};
+use tracing::{field::Empty, info, info_span};
+use tracing_glog::{Glog, GlogFields, Redaction};
+
+mod common;
+
+fn capture(redaction: Redaction, f: impl FnOnce()) -> Vec<String> {
+    let fields = GlogFields::default().with_redaction(redaction);
+    common::capture(Glog::default(), fields, f)
+}
+
+#[derive(Debug)]
+struct TestError(&'static str, Option<Box<TestError>>);
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Error for TestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1
+            .as_deref()
+            .map(|error| error as &(dyn Error + 'static))
+    }
+}
+
+#[test]
+fn names_ignore_case() {
+    let redaction = Redaction::default().with_name("password");
+    let lines = capture(redaction, || {
+        info!(password = "hunter2", user = "ann", "login");
+        info!(PassWord = 42, "login");
+    });
+    assert_eq!(
+        lines,
+        [
+            r#"login, password: <redacted>, user: "ann""#,
+            "login, PassWord: <redacted>",
+        ]
+    );
+}
+
+#[test]
+fn globs() {
+    let redaction = Redaction::default().with_glob("*token*").with_glob("a*b?c");
+    let lines = capture(redaction, || {
+        info!(api_token_id = 1, tokens = 2, toke = 3);
+        // matching these needs the `*` to match more than it first did.
+        info!(aXbYbZc = 1, abbbc = 2, abc = 3, abbc = 4);
+    });
+    assert_eq!(
+        lines,
+        [
+            "api_token_id: <redacted>, tokens: <redacted>, toke: 3",
+            "aXbYbZc: <redacted>, abbbc: <redacted>, abc: 3, abbc: <redacted>",
+        ]
+    );
+}
+
+#[test]
+fn keyed_hash() {
+    let key: [u8; 16] = std::array::from_fn(|i| i as u8);
+    let redaction = Redaction::default()
+        .with_name("secret")
+        .with_keyed_hash(key);
+    let lines = capture(redaction, || {
+        // the upper halves of the reference SipHash-2-4 test vectors for this key.
+        info!(secret = "");
+        info!(secret = "\0");
+        info!(secret = "a");
+        info!(secret = "a");
+    });
+    assert_eq!(lines[0], "secret: <redacted:726fdb47>");
+    assert_eq!(lines[1], "secret: <redacted:74f839c5>");
+    assert_eq!(lines[2], lines[3]);
+    assert_ne!(lines[2], lines[0]);
+}
+
+#[test]
+fn span_fields() {
+    let redaction = Redaction::default().with_name("password");
+    let lines = capture(redaction, || {
+        let span = info_span!("req", password = "hunter2", token = Empty, n = 1);
+        span.record("token", "abc");
+        span.record("password", "swordfish");
+        span.in_scope(|| info!("done"));
+    });
+    assert_eq!(
+        lines,
+        [r#"[req{password: <redacted>, n: 1, token: "abc"}] done"#]
+    );
+}
+
+#[test]
+fn recorded_span_fields() {
+    let redaction = Redaction::default().with_glob("*token");
+    let lines = capture(redaction, || {
+        let span = info_span!("req", api_token = Empty);
+        span.record("api_token", "abc");
+        span.in_scope(|| info!("done"));
+    });
+    assert_eq!(lines, ["[req{api_token: <redacted>}] done"]);
+}
+
+#[test]
+fn errors_by_name() {
+    let redaction = Redaction::default().with_name("error");
+    let lines = capture(redaction, || {
+        let error = TestError("outer", Some(Box::new(TestError("inner", None))));
+        info!(error = &error as &(dyn Error + 'static), "failed");
+    });
+    assert_eq!(lines, ["failed, error: <redacted>"]);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn value_patterns() {
+    let pattern = regex::Regex::new(r"\d{4}-\d{4}").unwrap();
+    let redaction = Redaction::default().with_value_pattern(pattern);
+    let lines = capture(redaction, || {
+        info!(card = "1234-5678", n = 12345678, "paid");
+        info!("card 1234-5678");
+        let span = info_span!("req", card = Empty, id = 7);
+        span.record("card", "1234-5678");
+        span.in_scope(|| info!("done"));
+    });
+    assert_eq!(
+        lines,
+        [
+            "paid, card: <redacted>, n: 12345678",
+            "<redacted>",
+            "[req{id: 7, card: <redacted>}] done",
+        ]
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn error_sources() {
+    let pattern = regex::Regex::new("secret").unwrap();
+    let redaction = Redaction::default().with_value_pattern(pattern);
+    let lines = capture(redaction, || {
+        let inner = TestError("bad secret", None);
+        let error = TestError("outer", Some(Box::new(inner)));
+        info!(error = &error as &(dyn Error + 'static), "failed");
+    });
+    assert_eq!(lines, ["failed, error: outer, error.sources: [<redacted>]"]);
+}
@@ -0,0 +1,146 @@
+use std::{thread, time::Duration};
+use tracing::{info, info_span};
+use tracing_glog::{Glog, GlogFields};
+use tracing_subscriber::fmt::format::FmtSpan;
+
+mod common;
+
+/// Returns the lines printed by `f`, with the layer emitting all span lifecycle events.
+fn capture(glog: Glog, fields: GlogFields, f: impl FnOnce()) -> Vec<String> {
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_span_events(FmtSpan::FULL)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(fields)
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    writer.lines()
+}
+
+/// Replaces the values of `time.busy` and `time.idle` in `line` with `T`, returning the
+/// line and the durations that were replaced.
+fn mask_timings(line: &str) -> (String, Vec<Duration>) {
+    let mut masked = line.to_string();
+    let mut timings = Vec::new();
+    for key in ["time.busy: ", "time.idle: ", "time.busy=", "time.idle="] {
+        let Some(start) = masked.find(key).map(|start| start + key.len()) else {
+            continue;
+        };
+        let end = masked[start..]
+            .find([',', ' '])
+            .map_or(masked.len(), |end| start + end);
+        timings.push(parse_duration(&masked[start..end]));
+        masked.replace_range(start..end, "T");
+    }
+    (masked, timings)
+}
+
+/// Parses the `Debug` output of a `Duration`, such as `1.5ms`.
+fn parse_duration(value: &str) -> Duration {
+    let (number, unit) = [("ns", 1e-9), ("µs", 1e-6), ("ms", 1e-3), ("s", 1.0)]
+        .into_iter()
+        .find_map(|(suffix, unit)| Some((value.strip_suffix(suffix)?, unit)))
+        .unwrap_or_else(|| panic!("not a duration: {value}"));
+    Duration::from_secs_f64(number.parse::<f64>().unwrap() * unit)
+}
+
+fn masked(lines: Vec<String>) -> Vec<String> {
+    lines.iter().map(|line| mask_timings(line).0).collect()
+}
+
+#[test]
+fn lifecycle() {
+    let lines = capture(Glog::default(), GlogFields::default(), || {
+        let outer = info_span!("outer", a = 1);
+        let _outer = outer.enter();
+        info_span!("inner", b = "x").in_scope(|| info!("hi"));
+    });
+    assert_eq!(
+        masked(lines),
+        [
+            "new outer{a: 1}",
+            "enter outer{a: 1}",
+            "[outer{a: 1}] new inner{b: \"x\"}",
+            "[outer{a: 1}] enter inner{b: \"x\"}",
+            "[outer{a: 1}, inner{b: \"x\"}] hi",
+            "[outer{a: 1}] exit inner{b: \"x\"}",
+            "[outer{a: 1}] close inner{b: \"x\"}, time.busy: T, time.idle: T",
+            "exit outer{a: 1}",
+            "close outer{a: 1}, time.busy: T, time.idle: T",
+        ]
+    );
+}
+
+#[test]
+fn timings() {
+    let lines = capture(Glog::default(), GlogFields::default(), || {
+        let span = info_span!("s");
+        thread::sleep(Duration::from_millis(20));
+        span.in_scope(|| thread::sleep(Duration::from_millis(40)));
+    });
+    let (close, timings) = mask_timings(lines.last().unwrap());
+    assert_eq!(close, "close s, time.busy: T, time.idle: T");
+    let (busy, idle) = (timings[0], timings[1]);
+    assert!(busy >= Duration::from_millis(39), "{busy:?}");
+    assert!(idle >= Duration::from_millis(19), "{idle:?}");
+    assert!(busy > idle, "{busy:?} {idle:?}");
+}
+
+#[test]
+fn filtered_by_kind() {
+    let run = || info_span!("s", a = 1).in_scope(|| info!("hi"));
+
+    let glog = Glog::default().with_span_events(FmtSpan::NEW | FmtSpan::CLOSE);
+    let lines = capture(glog, GlogFields::default(), run);
+    assert_eq!(
+        masked(lines),
+        [
+            "new s{a: 1}",
+            "[s{a: 1}] hi",
+            "close s{a: 1}, time.busy: T, time.idle: T"
+        ]
+    );
+
+    let glog = Glog::default().with_span_events(FmtSpan::ACTIVE);
+    let lines = capture(glog, GlogFields::default(), run);
+    assert_eq!(lines, ["enter s{a: 1}", "[s{a: 1}] hi", "exit s{a: 1}"]);
+
+    let glog = Glog::default().with_span_events(FmtSpan::NONE);
+    let lines = capture(glog, GlogFields::default(), run);
+    assert_eq!(lines, ["[s{a: 1}] hi"]);
+}
+
+#[test]
+fn message_is_not_formatted_as_a_field() {
+    // the span's fields are escaped and truncated once, when they are recorded, and the
+    // message that includes them is printed as is.
+    let fields = GlogFields::default().with_max_field_len(8);
+    let lines = capture(Glog::default(), fields, || {
+        info_span!("s", t = "a\tb", long = "abcdefghijk").in_scope(|| {})
+    });
+    assert_eq!(
+        masked(lines),
+        [
+            "new s{t: \"a\\tb\", long: \"abcdefg...[truncated 4 bytes]\"}",
+            "enter s{t: \"a\\tb\", long: \"abcdefg...[truncated 4 bytes]\"}",
+            "exit s{t: \"a\\tb\", long: \"abcdefg...[truncated 4 bytes]\"}",
+            "close s{t: \"a\\tb\", long: \"abcdefg...[truncated 4 bytes]\"}, time.busy: T, time.idle: T",
+        ]
+    );
+
+    let fields = GlogFields::default().use_logfmt(true);
+    let lines = capture(Glog::default(), fields, || {
+        info_span!("s", a = 1).in_scope(|| {})
+    });
+    assert_eq!(
+        masked(lines),
+        [
+            "new s{a=1}",
+            "enter s{a=1}",
+            "exit s{a=1}",
+            "close s{a=1} time.busy=T time.idle=T"
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+use tracing::{info, info_span};
+use tracing_glog::{Glog, GlogFields, SpanSelection};
+
+mod common;
+
+fn capture(selection: SpanSelection) -> Vec<String> {
+    let glog = Glog::default().with_span_selection(selection);
+    common::capture(glog, GlogFields::default(), || {
+        let a = info_span!(target: "app", "a", n = 1);
+        let b = info_span!(target: "hyper::proto::h1", parent: &a, "b");
+        let c = info_span!(target: "hyperx", parent: &b, "c");
+        let d = info_span!(target: "app::db", parent: &c, "d", n = 4);
+        d.in_scope(|| info!("m"));
+        info!("outside");
+    })
+}
+
+#[test]
+fn default_prints_all_spans() {
+    let lines = capture(SpanSelection::default());
+    assert_eq!(lines, ["[a{n: 1}, b, c, d{n: 4}] m", "outside"]);
+}
+
+#[test]
+fn allow_list() {
+    let lines = capture(SpanSelection::default().include_name("a").include_name("c"));
+    assert_eq!(lines, ["[a{n: 1}, c] m", "outside"]);
+
+    // targets match submodules, but not other targets that share a prefix.
+    let lines = capture(SpanSelection::default().include_target("hyper"));
+    assert_eq!(lines, ["[b] m", "outside"]);
+    let lines = capture(SpanSelection::default().include_target("app"));
+    assert_eq!(lines, ["[a{n: 1}, d{n: 4}] m", "outside"]);
+
+    // events whose spans are all excluded have no span context.
+    let lines = capture(SpanSelection::default().include_name("missing"));
+    assert_eq!(lines, ["m", "outside"]);
+}
+
+#[test]
+fn deny_list() {
+    let lines = capture(
+        SpanSelection::default()
+            .exclude_target("hyper")
+            .exclude_name("d"),
+    );
+    assert_eq!(lines, ["[a{n: 1}, c] m", "outside"]);
+
+    // exclusions take precedence over inclusions.
+    let selection = SpanSelection::default()
+        .include_target("app")
+        .exclude_target("app::db");
+    let lines = capture(selection);
+    assert_eq!(lines, ["[a{n: 1}] m", "outside"]);
+}
+
+#[test]
+fn innermost() {
+    let lines = capture(SpanSelection::default().with_innermost(1));
+    assert_eq!(lines, ["[d{n: 4}] m", "outside"]);
+    let lines = capture(SpanSelection::default().with_innermost(2));
+    assert_eq!(lines, ["[c, d{n: 4}] m", "outside"]);
+    let lines = capture(SpanSelection::default().with_innermost(10));
+    assert_eq!(lines, ["[a{n: 1}, b, c, d{n: 4}] m", "outside"]);
+
+    // the innermost of the selected spans are kept.
+    let selection = SpanSelection::default().exclude_name("d").with_innermost(1);
+    assert_eq!(capture(selection), ["[c] m", "outside"]);
+}
+
+#[test]
+fn max_depth() {
+    let lines = capture(SpanSelection::default().with_max_depth(4));
+    assert_eq!(lines, ["[a{n: 1}, b, c, d{n: 4}] m", "outside"]);
+    let lines = capture(SpanSelection::default().with_max_depth(3));
+    assert_eq!(lines, ["[a{n: 1}, ..., c, d{n: 4}] m", "outside"]);
+    let lines = capture(SpanSelection::default().with_max_depth(2));
+    assert_eq!(lines, ["[a{n: 1}, ..., d{n: 4}] m", "outside"]);
+    let lines = capture(SpanSelection::default().with_max_depth(1));
+    assert_eq!(lines, ["[..., d{n: 4}] m", "outside"]);
+    let lines = capture(SpanSelection::default().with_max_depth(0));
+    assert_eq!(lines, ["[...] m", "outside"]);
+}
+
+#[test]
+fn max_depth_of_innermost() {
+    let selection = SpanSelection::default().with_innermost(3).with_max_depth(2);
+    assert_eq!(capture(selection), ["[b, ..., d{n: 4}] m", "outside"]);
+}
@@ -0,0 +1,142 @@
+use tracing_glog::Theme;
+
+fn error(theme: &str) -> String {
+    theme.parse::<Theme>().unwrap_err().to_string()
+}
+
+#[test]
+fn malformed() {
+    assert_eq!(
+        error("field.key bold"),
+        "invalid theme: expected `element = style`, found `field.key bold`"
+    );
+    assert_eq!(
+        error("preset = sepia"),
+        "invalid theme: unknown preset `sepia`"
+    );
+    assert_eq!(
+        error("field = bold"),
+        "invalid theme: unknown element `field`"
+    );
+    assert_eq!(
+        error("message = bolder"),
+        "invalid theme: invalid style `bolder`"
+    );
+    assert_eq!(error("message = 256"), "invalid theme: invalid style `256`");
+    assert_eq!(
+        error("message = #12345"),
+        "invalid theme: invalid color `#12345`"
+    );
+    assert_eq!(
+        error("message = #12345g"),
+        "invalid theme: invalid color `#12345g`"
+    );
+    assert_eq!(
+        error("message = red on"),
+        "invalid theme: expected a color after `on` in `red on`"
+    );
+    assert_eq!(
+        error("message = bold; pid = on blue; target = x"),
+        "invalid theme: invalid style `x`"
+    );
+}
+
+#[test]
+fn empty_entries_and_comments() {
+    assert!(" ;\n# a comment\n; ".parse::<Theme>().is_ok());
+}
+
+#[cfg(feature = "ansi")]
+mod styles {
+    use tracing::Level;
+    use tracing_glog::{
+        nu_ansi_term::{Color, Style},
+        Theme, ThemeElement,
+    };
+
+    fn style(theme: &str, element: ThemeElement) -> Style {
+        theme.parse::<Theme>().unwrap().style(element)
+    }
+
+    #[test]
+    fn colors() {
+        let message = ThemeElement::Message;
+        assert_eq!(style("message = red", message), Color::Red.normal());
+        assert_eq!(
+            style("message = light-magenta", message),
+            Color::LightPurple.normal()
+        );
+        assert_eq!(style("message = 208", message), Color::Fixed(208).normal());
+        assert_eq!(style("message = 0", message), Color::Fixed(0).normal());
+        assert_eq!(
+            style("message = #8fBCbb", message),
+            Color::Rgb(0x8f, 0xbc, 0xbb).normal()
+        );
+        assert_eq!(
+            style("message = bold italic 208 on #000000", message),
+            Color::Fixed(208).on(Color::Rgb(0, 0, 0)).bold().italic()
+        );
+        assert_eq!(
+            style("message = on 236", message),
+            Style::new().on(Color::Fixed(236))
+        );
+        assert_eq!(style("message = bold plain", message), Style::new());
+    }
+
+    #[test]
+    fn entries_apply_in_order() {
+        let theme = "field.key = red\nfield.key = underline blue; message = green"
+            .parse::<Theme>()
+            .unwrap();
+        assert_eq!(theme.style(ThemeElement::FieldKey), Color::Blue.underline());
+        assert_eq!(theme.style(ThemeElement::Message), Color::Green.normal());
+        // unset elements keep their default style.
+        assert_eq!(
+            theme.style(ThemeElement::Level(Level::WARN)),
+            Theme::default().style(ThemeElement::Level(Level::WARN))
+        );
+    }
+
+    #[test]
+    fn presets() {
+        let elements = [
+            ThemeElement::Level(Level::TRACE),
+            ThemeElement::Level(Level::ERROR),
+            ThemeElement::Timestamp,
+            ThemeElement::ThreadName,
+            ThemeElement::SpanName,
+            ThemeElement::FieldKey,
+            ThemeElement::NumberValue,
+            ThemeElement::StringValue,
+            ThemeElement::BoolValue,
+        ];
+        for (preset, expected) in [
+            ("default", Theme::default()),
+            ("dark", Theme::dark()),
+            ("light", Theme::light()),
+            ("plain", Theme::plain()),
+        ] {
+            let theme: Theme = format!("preset = {preset}").parse().unwrap();
+            for element in elements {
+                assert_eq!(theme.style(element), expected.style(element), "{preset}");
+            }
+        }
+        assert_eq!(
+            style("preset = dark", ThemeElement::Level(Level::WARN)),
+            Color::LightYellow.bold()
+        );
+        assert_eq!(
+            style("preset = light", ThemeElement::StringValue),
+            Color::Fixed(94).normal()
+        );
+        // a preset replaces the entries before it.
+        assert_eq!(
+            style("message = red; preset = plain", ThemeElement::Message),
+            Style::new()
+        );
+        assert_eq!(
+            style("preset = light; message = red", ThemeElement::Message),
+            Color::Red.normal()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "tokio")]
+
+use tracing::{info, info_span, Instrument};
+use tracing_glog::{Glog, GlogFields, Layout};
+
+mod common;
+
+/// Returns the full lines printed by `f`, from the process ID on.
+fn capture(glog: Glog, f: impl FnOnce()) -> Vec<String> {
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    let pid = format!(" {} ", std::process::id());
+    writer
+        .output()
+        .lines()
+        .map(|line| line.split_once(&pid).unwrap().1.to_string())
+        .collect()
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn task_ids() {
+    let mut id = None;
+    let lines = capture(Glog::default().with_tokio_tasks(true), || {
+        runtime().block_on(async {
+            info!("in block_on");
+            let task = tokio::spawn(async {
+                info!("in task");
+                tokio::task::id()
+            });
+            id = Some(task.await.unwrap());
+        });
+        info!("outside");
+    });
+    let id = id.unwrap();
+    assert!(lines[0].starts_with("tests/"), "{lines:?}");
+    assert!(
+        lines[1].starts_with(&format!("task:{id} tests/")),
+        "{lines:?}"
+    );
+    assert!(lines[1].ends_with("] in task"), "{lines:?}");
+    assert!(lines[2].starts_with("tests/"), "{lines:?}");
+}
+
+#[test]
+fn disabled_by_default() {
+    let lines = capture(Glog::default(), || {
+        let task = async { info!("in task") };
+        runtime().block_on(async { tokio::spawn(task).await.unwrap() })
+    });
+    assert!(lines[0].starts_with("tests/"), "{lines:?}");
+}
+
+#[test]
+fn task_names() {
+    let lines = capture(Glog::default().with_tokio_tasks(true), || {
+        runtime().block_on(async {
+            // tokio creates these spans when compiled with `--cfg tokio_unstable`.
+            let spawn = |name| info_span!(target: "tokio::task", "runtime.spawn", task.name = name);
+            let named = async { info_span!("inner").in_scope(|| info!("named")) };
+            tokio::spawn(named.instrument(spawn("worker")))
+                .await
+                .unwrap();
+            let unnamed = async { info!("unnamed") };
+            tokio::spawn(unnamed.instrument(spawn(""))).await.unwrap();
+            let other = async { info!("other") };
+            let span = info_span!(target: "app", "runtime.spawn", task.name = "worker");
+            tokio::spawn(other.instrument(span)).await.unwrap();
+        });
+    });
+    let tasks: Vec<_> = lines
+        .iter()
+        .map(|line| line.split_once(' ').unwrap().0)
+        .collect();
+    assert!(
+        tasks[0].starts_with("task:") && tasks[0].ends_with("(worker)"),
+        "{lines:?}"
+    );
+    assert!(!tasks[1].contains('('), "{lines:?}");
+    assert!(!tasks[2].contains('('), "{lines:?}");
+}
+
+#[test]
+fn layout() {
+    let layout = Layout::parse("{task}] {message}").unwrap();
+    let glog = Glog::default().with_tokio_tasks(true).with_layout(layout);
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default())
+        .finish();
+    let id = tracing::subscriber::with_default(subscriber, || {
+        info!("outside");
+        let task = async {
+            info!("inside");
+            tokio::task::id()
+        };
+        runtime().block_on(async { tokio::spawn(task).await.unwrap() })
+    });
+    assert_eq!(writer.output(), format!("] outside\ntask:{id}] inside\n"));
+}
@@ -0,0 +1,80 @@
+use tracing::{info, info_span};
+use tracing_glog::{Escaping, Glog, GlogFields, Layout, Multiline};
+
+mod common;
+
+fn capture_fields(max: usize, f: impl FnOnce()) -> Vec<String> {
+    let fields = GlogFields::default().with_max_field_len(max);
+    common::capture(Glog::default(), fields, f)
+}
+
+#[test]
+fn fields_are_cut_between_characters() {
+    let lines = capture_fields(9, || {
+        info!(a = "héllo wörld");
+        // `本` ends at byte 7, and `語` would end at byte 10.
+        info!(b = "日本語");
+        info!(n = 12345678901_u64);
+    });
+    assert_eq!(
+        lines,
+        [
+            r#"a: "héllo w...[truncated 5 bytes]""#,
+            r#"b: "日本...[truncated 3 bytes]""#,
+            "n: 123456789...[truncated 2 bytes]",
+        ]
+    );
+    let lines = capture_fields(5, || info!(b = "日本語"));
+    assert_eq!(lines, [r#"b: "日...[truncated 6 bytes]""#]);
+}
+
+#[test]
+fn messages_are_cut() {
+    let lines = capture_fields(5, || info!("a message"));
+    assert_eq!(lines, ["a mes...[truncated 4 bytes]"]);
+}
+
+#[test]
+fn escape_codes_are_not_counted() {
+    let fields = GlogFields::default()
+        .compact()
+        .with_escaping(Escaping::Never)
+        .with_max_field_len(5);
+    let lines = common::capture(Glog::default(), fields, || {
+        info!(a = "\x1b[31mred\x1b[0m text");
+    });
+    assert_eq!(lines, ["a:\x1b[31mred\x1b[0m t...[truncated 3 bytes]"]);
+}
+
+#[test]
+fn span_context_is_cut() {
+    let glog = Glog::default().with_max_span_context_len(12);
+    let lines = common::capture(glog, GlogFields::default(), || {
+        info_span!("outer", a = 1).in_scope(|| info_span!("inner").in_scope(|| info!("x")));
+    });
+    assert_eq!(lines, ["[outer{a: 1}...[truncated 7 bytes]] x"]);
+}
+
+#[test]
+fn lines_are_cut_separately() {
+    let layout = Layout::parse("{level}] {message}").unwrap();
+    let glog = Glog::default()
+        .with_layout(layout)
+        .with_multiline(Multiline::RepeatPrefix)
+        .with_max_line_len(20);
+    let writer = common::TestWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .event_format(glog)
+        .fmt_fields(GlogFields::default())
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        info!("a\nsecond line that is long\nthird");
+    });
+    // every line keeps its prefix, and only the long one is cut.
+    assert_eq!(
+        writer.output(),
+        "I] a\nI] [...] second line...[truncated 13 bytes]\nI] [...] third\n"
+    );
+}
@@ -0,0 +1,166 @@
+use std::time::Duration;
+use tracing::info;
+use tracing_glog::{BytesFormat, Glog, GlogFields, ValueFormat};
+
+mod common;
+
+fn capture(values: ValueFormat, f: impl FnOnce()) -> Vec<String> {
+    let fields = GlogFields::default().with_value_format(values);
+    common::capture(Glog::default(), fields, f)
+}
+
+#[test]
+fn float_precision() {
+    let values = ValueFormat::default().with_float_precision(2);
+    let lines = capture(values, || {
+        info!(
+            a = 1.0f64,
+            b = 2.345f64,
+            c = -0.001f64,
+            d = 1e20f64,
+            e = f64::NAN,
+            "m"
+        )
+    });
+    assert_eq!(lines, ["m, a: 1.00, b: 2.35, c: -0.00, d: 1.00e20, e: NaN"]);
+
+    let lines = capture(ValueFormat::default(), || {
+        info!(a = 1.0f64, b = 2.345f64, "m")
+    });
+    assert_eq!(lines, ["m, a: 1.0, b: 2.345"]);
+}
+
+#[test]
+fn bytes() {
+    let bytes: &[u8] = &[0xde, 0xad, 0xbe, 0xef, 0x00];
+    let capture_bytes = |format| {
+        let values = ValueFormat::default().with_bytes(format);
+        capture(values, || {
+            info!(
+                a = bytes,
+                b = &bytes[..1],
+                c = &bytes[..2],
+                d = &b""[..],
+                "m"
+            )
+        })
+    };
+    assert_eq!(
+        capture_bytes(BytesFormat::List),
+        ["m, a: [de ad be ef 00], b: [de], c: [de ad], d: []"]
+    );
+    assert_eq!(
+        capture_bytes(BytesFormat::Hex),
+        ["m, a: deadbeef00, b: de, c: dead, d: "]
+    );
+    assert_eq!(
+        capture_bytes(BytesFormat::Base64),
+        ["m, a: 3q2+7wA=, b: 3g==, c: 3q0=, d: "]
+    );
+}
+
+#[test]
+fn human_durations() {
+    let values = ValueFormat::default().with_human_durations(true);
+    let lines = capture(values, || {
+        info!(
+            a = ?Duration::from_millis(250),
+            b = ?Duration::from_secs(59),
+            c = ?Duration::from_secs(60),
+            d = ?Duration::from_millis(3_723_500),
+            e = ?Duration::from_secs(90_061),
+            f = ?Duration::from_nanos(3_600_000_000_001),
+            "{:?}",
+            Duration::from_secs(120)
+        )
+    });
+    assert_eq!(
+        lines,
+        ["120s, a: 250ms, b: 59s, c: 1m0s, d: 1h2m3.5s, e: 1d1h1m1s, f: 1h0m0.000000001s"]
+    );
+
+    let lines = capture(
+        ValueFormat::default(),
+        || info!(a = ?Duration::from_secs(120), "m"),
+    );
+    assert_eq!(lines, ["m, a: 120s"]);
+}
+
+#[test]
+fn byte_sizes() {
+    let values = ValueFormat::default().with_byte_size_field("*_bytes");
+    let lines = capture(values, || {
+        info!(
+            a_bytes = 512u64,
+            b_bytes = 1024u64,
+            c_bytes = 1536u64,
+            d_bytes = 5_000_000u64,
+            e_bytes = -2048i64,
+            f_bytes = u128::MAX,
+            other = 2048u64,
+            "m"
+        )
+    });
+    assert_eq!(
+        lines,
+        [
+            "m, a_bytes: 512B, b_bytes: 1KiB, c_bytes: 1.5KiB, d_bytes: 4.8MiB, \
+          e_bytes: -2KiB, f_bytes: 281474976710656YiB, other: 2048"
+        ]
+    );
+}
+
+#[test]
+fn thousands_separator() {
+    let values = ValueFormat::default()
+        .with_thousands_separator('_')
+        .with_float_precision(1);
+    let lines = capture(values, || {
+        info!(
+            a = 999u64,
+            b = 1000u64,
+            c = -1_234_567i64,
+            d = 1234.5f64,
+            e = -100_000i64,
+            "m"
+        );
+        info!(min = i128::MIN, max = u128::MAX, i64_min = i64::MIN, "m");
+    });
+    assert_eq!(
+        lines,
+        [
+            "m, a: 999, b: 1_000, c: -1_234_567, d: 1_234.5, e: -100_000",
+            "m, min: -170_141_183_460_469_231_731_687_303_715_884_105_728, \
+             max: 340_282_366_920_938_463_463_374_607_431_768_211_455, \
+             i64_min: -9_223_372_036_854_775_808",
+        ]
+    );
+}
+
+#[cfg(feature = "ansi")]
+#[test]
+fn type_based_colors() {
+    use tracing_glog::{nu_ansi_term::Color, Theme, ThemeElement};
+
+    let theme = Theme::plain()
+        .with_style(ThemeElement::NumberValue, Color::Cyan.normal())
+        .with_style(ThemeElement::StringValue, Color::Green.normal())
+        .with_style(ThemeElement::BoolValue, Color::Purple.normal());
+    let glog = Glog::default().with_theme(theme.clone());
+    let fields = GlogFields::default()
+        .with_theme(theme)
+        .with_value_format(ValueFormat::default().with_human_durations(true));
+    let lines = common::capture_with_ansi(
+        true,
+        glog,
+        fields,
+        || info!(n = 1u64, f = 1.5f64, s = "x", b = true, d = ?Duration::from_secs(60), o = ?(), "m"),
+    );
+    assert_eq!(
+        lines,
+        [
+            "m, n: \x1b[36m1\x1b[0m, f: \x1b[36m1.5\x1b[0m, s: \x1b[32m\"x\"\x1b[0m, \
+          b: \x1b[35mtrue\x1b[0m, d: \x1b[36m1m0s\x1b[0m, o: ()"
+        ]
+    );
+}